@@ -1,9 +1,3 @@
-DROP TABLE IF EXISTS srs_reviews;
-DROP TABLE IF EXISTS deck_words;
-DROP TABLE IF EXISTS words;
-DROP TABLE IF EXISTS decks;
-DROP TABLE IF EXISTS users;
-
 CREATE TABLE users (
     user_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,  
     email TEXT NOT NULL UNIQUE,
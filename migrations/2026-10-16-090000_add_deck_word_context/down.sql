@@ -0,0 +1,3 @@
+ALTER TABLE deck_words DROP COLUMN context_timestamp_ms;
+ALTER TABLE deck_words DROP COLUMN context_source;
+ALTER TABLE deck_words DROP COLUMN context_line;
@@ -0,0 +1,4 @@
+-- Example context a word was mined from (e.g. a subtitle line)
+ALTER TABLE deck_words ADD COLUMN context_line TEXT;
+ALTER TABLE deck_words ADD COLUMN context_source TEXT;
+ALTER TABLE deck_words ADD COLUMN context_timestamp_ms INTEGER;
@@ -0,0 +1,7 @@
+CREATE TABLE sessions (
+    id TEXT PRIMARY KEY NOT NULL,
+    data TEXT NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_sessions_expires_at ON sessions(expires_at);
@@ -0,0 +1,30 @@
+CREATE TABLE review_log (
+    log_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    user_id INTEGER NOT NULL,
+    word_id INTEGER NOT NULL,
+    deck_id INTEGER NOT NULL,
+    reviewed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    rating INTEGER NOT NULL,
+    previous_interval INTEGER,
+    new_interval INTEGER NOT NULL,
+    previous_ease REAL,
+    new_ease REAL NOT NULL,
+    elapsed_days INTEGER,
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (word_id) REFERENCES words(word_id) ON DELETE CASCADE,
+    FOREIGN KEY (deck_id) REFERENCES decks(deck_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_review_log_user ON review_log(user_id, reviewed_at);
+CREATE INDEX idx_review_log_word ON review_log(user_id, word_id);
+
+-- The current state of every card becomes its first log entry
+INSERT INTO review_log (
+    user_id, word_id, deck_id, reviewed_at, rating,
+    previous_interval, new_interval, previous_ease, new_ease, elapsed_days
+)
+SELECT user_id, word_id, deck_id, review_date, performance,
+       NULL, interval, NULL, ease_factor, NULL
+FROM srs_reviews
+ORDER BY review_date;
@@ -0,0 +1,4 @@
+ALTER TABLE srs_reviews DROP COLUMN difficulty;
+ALTER TABLE srs_reviews DROP COLUMN stability;
+
+DROP TABLE IF EXISTS user_settings;
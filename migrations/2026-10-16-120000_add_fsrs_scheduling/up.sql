@@ -0,0 +1,11 @@
+CREATE TABLE user_settings (
+    user_id INTEGER PRIMARY KEY NOT NULL,
+    scheduler TEXT NOT NULL DEFAULT 'sm2',
+    desired_retention REAL NOT NULL DEFAULT 0.9,
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+-- FSRS memory state, NULL for cards scheduled with SM-2
+ALTER TABLE srs_reviews ADD COLUMN stability REAL;
+ALTER TABLE srs_reviews ADD COLUMN difficulty REAL;
@@ -0,0 +1,2 @@
+-- Per-deck scheduler override, NULL uses the owner's setting
+ALTER TABLE decks ADD COLUMN scheduler TEXT;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_decks_public;
+
+ALTER TABLE decks DROP COLUMN source_deck_id;
@@ -0,0 +1,4 @@
+-- Deck a clone was copied from, set together with duplicated_check
+ALTER TABLE decks ADD COLUMN source_deck_id INTEGER REFERENCES decks(deck_id) ON DELETE SET NULL;
+
+CREATE INDEX idx_decks_public ON decks(privacy_value);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_deck_likes_deck;
+
+DROP TABLE IF EXISTS deck_likes;
@@ -0,0 +1,15 @@
+-- One row per user who liked a deck, so a deck can be liked only once per user
+CREATE TABLE deck_likes (
+    user_id INTEGER NOT NULL,
+    deck_id INTEGER NOT NULL,
+    liked_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (user_id, deck_id),
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (deck_id) REFERENCES decks(deck_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_deck_likes_deck ON deck_likes(deck_id, liked_at);
+
+-- like_count is kept in step with deck_likes from now on, and no likes exist yet
+UPDATE decks SET like_count = 0;
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_deck_words_position;
+
+ALTER TABLE deck_words DROP COLUMN position;
+
+ALTER TABLE decks DROP COLUMN cover_word_id;
+ALTER TABLE decks DROP COLUMN description;
@@ -0,0 +1,14 @@
+ALTER TABLE decks ADD COLUMN description TEXT;
+-- Word shown to represent the deck in listings
+ALTER TABLE decks ADD COLUMN cover_word_id INTEGER REFERENCES words(word_id) ON DELETE SET NULL;
+
+-- Order of words within a deck, starting at 0
+ALTER TABLE deck_words ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+-- Existing words keep the order they were added in
+UPDATE deck_words SET position = (
+    SELECT COUNT(*) FROM deck_words AS earlier
+    WHERE earlier.deck_id = deck_words.deck_id AND earlier.rowid < deck_words.rowid
+);
+
+CREATE INDEX idx_deck_words_position ON deck_words(deck_id, position);
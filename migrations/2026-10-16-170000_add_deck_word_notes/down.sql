@@ -0,0 +1,4 @@
+ALTER TABLE deck_words DROP COLUMN example;
+ALTER TABLE deck_words DROP COLUMN mnemonic;
+ALTER TABLE deck_words DROP COLUMN note;
+ALTER TABLE deck_words DROP COLUMN custom_definition;
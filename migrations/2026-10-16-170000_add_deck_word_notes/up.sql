@@ -0,0 +1,5 @@
+-- Per-deck additions to a shared word, NULL when not set
+ALTER TABLE deck_words ADD COLUMN custom_definition TEXT;
+ALTER TABLE deck_words ADD COLUMN note TEXT;
+ALTER TABLE deck_words ADD COLUMN mnemonic TEXT;
+ALTER TABLE deck_words ADD COLUMN example TEXT;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_deck_subscriptions_deck;
+
+DROP TABLE IF EXISTS deck_subscriptions;
@@ -0,0 +1,12 @@
+-- Public decks a user studies without owning a copy, such as the HSK system decks
+CREATE TABLE deck_subscriptions (
+    user_id INTEGER NOT NULL,
+    deck_id INTEGER NOT NULL,
+    subscribed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (user_id, deck_id),
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (deck_id) REFERENCES decks(deck_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_deck_subscriptions_deck ON deck_subscriptions(deck_id);
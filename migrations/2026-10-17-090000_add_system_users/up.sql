@@ -0,0 +1,2 @@
+-- Accounts owned by the application, such as the owner of the HSK decks. Nobody can log in as them.
+ALTER TABLE users ADD COLUMN is_system BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,85 @@
+use diesel::dsl::sql;
+use diesel::migration::{MigrationSource, MigrationVersion};
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use diesel::sqlite::Sqlite;
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+use crate::data::models::MigrationError;
+
+/// Every migration under `migrations/`, compiled into the binary
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Applies every pending migration, returning the versions that ran.
+/// Each migration runs in its own transaction, so a failure leaves the
+/// database at the last migration that succeeded.
+pub fn run(conn: &mut SqliteConnection) -> Result<Vec<String>, MigrationError> {
+    let pending = check(conn)?;
+    if pending.is_empty() {
+        return Ok(pending);
+    }
+
+    conn.run_pending_migrations(MIGRATIONS)?;
+    Ok(pending)
+}
+
+/// Refuses to continue unless the database is fully migrated. With `auto`,
+/// pending migrations are applied first.
+pub fn prepare(conn: &mut SqliteConnection, auto: bool) -> Result<Vec<String>, MigrationError> {
+    if auto {
+        return run(conn);
+    }
+
+    let pending = check(conn)?;
+    if !pending.is_empty() {
+        return Err(MigrationError::Pending(pending));
+    }
+    Ok(pending)
+}
+
+/// Pending migrations, failing if the recorded history does not match the
+/// embedded migrations
+fn check(conn: &mut SqliteConnection) -> Result<Vec<String>, MigrationError> {
+    let known: Vec<MigrationVersion<'static>> = MigrationSource::<Sqlite>::migrations(&MIGRATIONS)?
+        .iter()
+        .map(|migration| migration.name().version().as_owned())
+        .collect();
+
+    // A database created before migrations were tracked would be wiped by the first one
+    let applied = conn.applied_migrations()?;
+    if applied.is_empty() && has_table(conn, "users")? {
+        return Err(MigrationError::Untracked);
+    }
+
+    let unknown: Vec<String> = applied
+        .iter()
+        .filter(|version| !known.contains(version))
+        .map(ToString::to_string)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(MigrationError::Unknown(unknown));
+    }
+
+    let pending: Vec<&MigrationVersion> = known.iter().filter(|version| !applied.contains(version)).collect();
+    let latest_applied = applied.iter().max();
+    let skipped: Vec<String> = pending
+        .iter()
+        .filter(|version| latest_applied.is_some_and(|latest| **version < latest))
+        .map(ToString::to_string)
+        .collect();
+    if !skipped.is_empty() {
+        return Err(MigrationError::OutOfOrder(skipped));
+    }
+
+    Ok(pending.into_iter().map(ToString::to_string).collect())
+}
+
+fn has_table(conn: &mut SqliteConnection, name: &str) -> QueryResult<bool> {
+    let count = diesel::select(sql::<BigInt>(
+        "(SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ",
+    )
+    .bind::<diesel::sql_types::Text, _>(name)
+    .sql(")"))
+    .get_result::<i64>(conn)?;
+    Ok(count > 0)
+}
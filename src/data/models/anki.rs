@@ -0,0 +1,105 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// Anki package errors
+#[derive(Error, Debug)]
+pub enum AnkiError {
+    #[error("Database error: {0}")]
+    Database(#[from] diesel::result::Error),
+    #[error("Could not open Anki collection: {0}")]
+    Connection(#[from] diesel::ConnectionError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid Anki package: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Package contains no Anki collection")]
+    MissingCollection,
+    #[error("Package uses the latest Anki format, export it with \"Support older Anki versions\" enabled")]
+    UnsupportedCollection,
+    #[error("Anki collection is larger than {0} MB once extracted")]
+    CollectionTooLarge(u64),
+    #[error("Invalid Anki collection: {0}")]
+    InvalidCollection(String),
+    #[error("Note type {0} not found in package")]
+    UnknownNoteType(i64),
+    #[error("Field \"{0}\" not found in note type")]
+    UnknownField(String),
+}
+
+/// Query parameters for the Anki export endpoint
+#[derive(Deserialize)]
+pub struct AnkiExportParams {
+    #[serde(default)]
+    pub scheduling: bool, // Include the SRS state of every card
+}
+
+/// A deck word to be written as an Anki note
+#[derive(Debug)]
+pub struct AnkiExportNote {
+    pub word_id: i32,
+    pub simplified: String,
+    pub traditional: Option<String>,
+    pub pinyin: String,
+    pub definition: String,
+    pub schedule: Option<AnkiExportSchedule>, // None exports the card as new
+}
+
+/// SRS state of a card, converted to Anki's review queue on export
+#[derive(Debug)]
+pub struct AnkiExportSchedule {
+    pub next_review_date: NaiveDateTime,
+    pub interval: i32,
+    pub ease_factor: f32,
+    pub stability: Option<f32>,
+    pub difficulty: Option<f32>,
+    pub reps: i32,   // Number of logged reviews
+    pub lapses: i32, // Number of logged "again" answers after the first review
+}
+
+/// A note type found in an uploaded package
+#[derive(Debug, Serialize)]
+pub struct AnkiNoteType {
+    pub id: i64,
+    pub name: String,
+    pub fields: Vec<String>,        // Field names, in order
+    pub note_count: usize,
+    pub samples: Vec<Vec<String>>,  // Field values of the first few notes, HTML removed
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnkiPreview {
+    pub decks: Vec<String>,             // Deck names in the package
+    pub note_types: Vec<AnkiNoteType>,
+    pub scheduled_cards: usize,         // Cards with review history
+}
+
+/// Which note fields hold each part of a word, by field name
+#[derive(Deserialize)]
+pub struct AnkiFieldMapping {
+    pub simplified: String,
+    pub traditional: Option<String>,
+    pub pinyin: Option<String>,     // Filled from CC-CEDICT when missing
+    pub definition: Option<String>, // Filled from CC-CEDICT when missing
+}
+
+/// Options sent alongside the uploaded package, as the `options` form field
+#[derive(Deserialize)]
+pub struct AnkiImportOptions {
+    pub name: String,
+    #[serde(default)]
+    pub privacy_value: bool,
+    pub note_type_id: Option<i64>, // Defaults to the note type with the most notes
+    pub mapping: AnkiFieldMapping,
+    #[serde(default)]
+    pub reset_scheduling: bool,    // Import every card as new
+}
+
+/// Review state of an imported card, converted from Anki's interval and ease
+#[derive(Debug, Clone, Copy)]
+pub struct ImportedSchedule {
+    pub review_date: NaiveDateTime,
+    pub next_review_date: NaiveDateTime,
+    pub interval: i32,
+    pub ease_factor: f32,
+}
\ No newline at end of file
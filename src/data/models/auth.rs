@@ -32,6 +32,7 @@ pub enum RegisterError {
     SessionError(String),
 }
 
+#[allow(dead_code)]
 #[derive(Error, Debug)]
 pub enum AuthError {
     #[error(transparent)]
@@ -0,0 +1,114 @@
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use thiserror::Error;
+
+// Configuration errors
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("Invalid value {value:?} for {name}: {reason}")]
+    Env {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+    #[error("Invalid {field}: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+/// Application settings read from `config.toml` and `ZWCD_*` environment variables
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub dictionary: DictionaryConfig,
+    pub paths: PathsConfig,
+    pub session: SessionConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: SocketAddr, // Address the listener binds to
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: SocketAddr::from(([127, 0, 0, 1], 5000)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub auto_migrate: bool, // Apply pending migrations at startup instead of refusing to start
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: "sqlite://site.db".to_string(),
+            auto_migrate: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DictionaryConfig {
+    pub path: PathBuf, // CC-CEDICT file in `cedict_ts.u8` format
+}
+
+impl Default for DictionaryConfig {
+    fn default() -> Self {
+        DictionaryConfig {
+            path: PathBuf::from("src/data/cedict_ts.u8"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    pub templates: PathBuf,    // Directory of the Tera templates
+    pub static_files: PathBuf, // Directory served under `/static`
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        PathsConfig {
+            templates: PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/templates")),
+            static_files: PathBuf::from("src/static"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    pub expiry_days: i64, // Days of inactivity before a session expires
+    pub secure: bool,     // Send the session cookie over HTTPS only
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            expiry_days: 1,
+            secure: false,
+        }
+    }
+}
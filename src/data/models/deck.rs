@@ -1,6 +1,8 @@
 use diesel::Queryable;
 use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::data::models::{SchedulerKind, WordContext};
 
 /// Represents a word that belongs to a deck
 #[derive(Serialize)]
@@ -12,6 +14,38 @@ pub struct DeckWord {
     pub pinyin: String,       // Pinyin pronunciation
     pub definition: String,   // English definition(s)
     pub deck_id: i32,         // Deck ID this word belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<WordContext>, // Line the word was imported from (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<DeckWordNotes>, // Deck-specific additions (optional)
+}
+
+/// Additions to a shared word that only apply within one deck
+#[derive(Serialize, Deserialize, Default)]
+pub struct DeckWordNotes {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>, // Replaces the dictionary definition when studying
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,       // Personal note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,    // Example sentence
+}
+
+impl DeckWordNotes {
+    /// Rebuilds the notes from the nullable `deck_words` columns, `None` when none are set
+    pub fn from_columns(
+        definition: Option<String>,
+        note: Option<String>,
+        mnemonic: Option<String>,
+        example: Option<String>,
+    ) -> Option<Self> {
+        if definition.is_none() && note.is_none() && mnemonic.is_none() && example.is_none() {
+            return None;
+        }
+        Some(DeckWordNotes { definition, note, mnemonic, example })
+    }
 }
 
 /// Represents a deck with all its words
@@ -19,7 +53,11 @@ pub struct DeckWord {
 pub struct DeckWithWords {
     pub id: i32,            // Deck ID
     pub name: String,       // Deck name
-    pub words: Vec<DeckWord>, // Words in this deck
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>, // Deck description (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_word_id: Option<i32>,  // Word representing the deck (optional)
+    pub words: Vec<DeckWord>, // Words in this deck, in deck order
 }
 
 /// Request payload for deck-specific operations
@@ -34,6 +72,13 @@ pub struct Deck {
     pub id: i32,          // Deck ID
     pub name: String,     // Deck name
     pub privacy_value: bool, // Privacy setting
+    pub tags: Vec<String>,   // Deck tags, sorted alphabetically
+}
+
+/// Query parameters for listing the current user's decks
+#[derive(Deserialize)]
+pub struct DeckListQuery {
+    pub tags: Option<String>, // Comma-separated, only decks with all of them
 }
 
 /// Request payload for creating a new deck
@@ -88,3 +133,132 @@ pub struct ReviewRequest {
 pub struct UpdatePrivacyRequest {
     pub privacy_value: bool,
 }
+
+/// Request payload for editing a deck. Omitted fields are left unchanged,
+/// `null` clears the description or cover word.
+#[derive(Deserialize)]
+pub struct UpdateDeckRequest {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub cover_word_id: Option<Option<i32>>,
+}
+
+/// Tells an explicit `null` apart from a missing field
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Request payload for reordering the words of a deck
+#[derive(Deserialize)]
+pub struct ReorderDeckRequest {
+    pub word_ids: Vec<i32>, // Listed words first, the rest keep their relative order
+}
+
+/// Order of words in a study session
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StudyOrder {
+    #[default]
+    Due,  // New words first, then by next review date
+    Deck, // Deck order as set by reordering
+}
+
+/// Query parameters for starting a study session
+#[derive(Deserialize)]
+pub struct StudySessionQuery {
+    #[serde(default)]
+    pub order: StudyOrder,
+}
+
+/// Request payload for overriding a deck's scheduler, `null` falls back to the user's setting
+#[derive(Deserialize)]
+pub struct UpdateDeckSchedulerRequest {
+    pub scheduler: Option<SchedulerKind>,
+}
+
+/// Order of public deck listings
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicDeckSort {
+    #[default]
+    Likes,  // Most liked first
+    Newest, // Most recently created first
+    Name,   // Alphabetical
+    Size,   // Most words first
+    RecentLikes, // Most liked over the last week
+}
+
+/// Query parameters for listing and searching public decks
+#[derive(Deserialize)]
+pub struct PublicDeckQuery {
+    pub q: Option<String>,       // Substring of the deck name
+    pub tags: Option<String>,    // Comma-separated, only decks with all of them
+    pub min_words: Option<i64>,
+    pub max_words: Option<i64>,
+    pub min_likes: Option<i32>,
+    #[serde(default)]
+    pub sort: PublicDeckSort,
+    pub page: Option<i64>,       // One-based, defaults to 1
+    pub per_page: Option<i64>,   // Defaults to 20, at most 100
+}
+
+/// A public deck as shown in listings
+#[derive(Serialize)]
+pub struct PublicDeckSummary {
+    pub id: i32,
+    pub name: String,
+    pub word_count: i64,
+    pub like_count: i32,
+    pub tags: Vec<String>,
+    pub user_made: bool,     // False for system decks
+    pub is_clone: bool,      // Copied from another public deck
+    pub liked: bool,         // Liked by the current user
+}
+
+#[derive(Serialize)]
+pub struct PublicDeckList {
+    pub decks: Vec<PublicDeckSummary>,
+    pub page: i64,
+    pub per_page: i64,
+    pub has_more: bool,
+}
+
+/// Like state of a deck after liking or unliking it
+#[derive(Serialize)]
+pub struct DeckLikeStatus {
+    pub deck_id: i32,
+    pub liked: bool,      // Liked by the current user
+    pub like_count: i32,  // Likes from all users
+}
+
+/// A public deck the current user has liked
+#[derive(Serialize, Queryable)]
+pub struct LikedDeck {
+    pub id: i32,
+    pub name: String,
+    pub like_count: i32,
+    pub liked_at: NaiveDateTime, // When the current user liked it
+}
+
+/// A public deck the current user has subscribed to
+#[derive(Serialize, Queryable)]
+pub struct SubscribedDeck {
+    pub id: i32,
+    pub name: String,
+    pub user_made: bool,              // False for system decks
+    pub subscribed_at: NaiveDateTime,
+}
+
+/// Result of cloning a public deck
+#[derive(Serialize)]
+pub struct ClonedDeck {
+    pub deck_id: i32,        // The new copy, owned by the current user
+    pub source_deck_id: i32,
+    pub word_count: usize,
+}
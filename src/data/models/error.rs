@@ -0,0 +1,81 @@
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::data::models::{AnkiError, DeckTagError, SubtitleError, WordListError};
+
+// JSON API errors
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Not logged in")]
+    Unauthorized,
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{message}")]
+    Validation {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    #[error("{0}")]
+    Conflict(String),
+    #[error("Database error: {0}")]
+    Database(#[from] diesel::result::Error),
+    #[error("Connection error: {0}")]
+    Pool(#[from] diesel::r2d2::PoolError),
+    #[error("Session error: {0}")]
+    Session(#[from] tower_sessions::session::Error),
+    #[error("{0}")]
+    Internal(String),
+}
+
+/// A request field that failed validation
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl AppError {
+    /// Validation error for a single request field
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        AppError::Validation {
+            fields: vec![FieldError {
+                field: field.to_string(),
+                message: message.clone(),
+            }],
+            message,
+        }
+    }
+}
+
+impl From<AnkiError> for AppError {
+    fn from(error: AnkiError) -> Self {
+        match error {
+            AnkiError::Database(e) => AppError::Database(e),
+            AnkiError::Io(e) => AppError::Internal(format!("File error: {}", e)),
+            e => AppError::BadRequest(e.to_string()),
+        }
+    }
+}
+
+impl From<SubtitleError> for AppError {
+    fn from(error: SubtitleError) -> Self {
+        AppError::BadRequest(error.to_string())
+    }
+}
+
+impl From<WordListError> for AppError {
+    fn from(error: WordListError) -> Self {
+        AppError::BadRequest(error.to_string())
+    }
+}
+
+impl From<DeckTagError> for AppError {
+    fn from(error: DeckTagError) -> Self {
+        AppError::invalid_field("tags", error.to_string())
+    }
+}
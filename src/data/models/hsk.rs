@@ -0,0 +1,73 @@
+use serde::Deserialize;
+use thiserror::Error;
+
+// HSK word list import errors
+#[derive(Error, Debug)]
+pub enum HskError {
+    #[error("Failed to read HSK word list: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid HSK word list: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] diesel::result::Error),
+    #[error("Connection error: {0}")]
+    Pool(#[from] diesel::r2d2::PoolError),
+    #[error("Hashing error: {0}")]
+    Hashing(#[from] bcrypt::BcryptError),
+    #[error("{0} belongs to a registered user, refusing to give it the system decks")]
+    SystemEmailTaken(&'static str),
+}
+
+/// HSK syllabus a level belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HskVersion {
+    V2, // HSK 2.0 (2010), levels 1-6
+    V3, // HSK 3.0 (2021), levels 1-6 and the combined 7-9 band
+}
+
+impl HskVersion {
+    pub fn label(self) -> &'static str {
+        match self {
+            HskVersion::V2 => "HSK 2.0",
+            HskVersion::V3 => "HSK 3.0",
+        }
+    }
+}
+
+/// Entry of the complete HSK vocabulary list (`complete.json`)
+#[derive(Debug, Deserialize)]
+pub struct HskEntry {
+    pub simplified: String,
+    #[serde(rename = "level", default)]
+    pub levels: Vec<String>, // Such as "old-3" for HSK 2.0 or "new-7" for HSK 3.0
+    #[serde(default)]
+    pub forms: Vec<HskForm>,
+}
+
+/// One reading of an HSK entry
+#[derive(Debug, Deserialize)]
+pub struct HskForm {
+    #[serde(default)]
+    pub traditional: Option<String>,
+    #[serde(default)]
+    pub transcriptions: HskTranscriptions,
+    #[serde(default)]
+    pub meanings: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HskTranscriptions {
+    #[serde(default)]
+    pub numeric: Option<String>, // Pinyin with tone numbers
+}
+
+/// Outcome of importing one HSK level
+#[derive(Debug)]
+pub struct HskDeckReport {
+    pub name: String,
+    pub deck_id: i32,
+    pub created: bool,      // False when the deck already existed and was left as is
+    pub words: usize,       // Words added to the deck
+    pub from_list: usize,   // Words not in CC-CEDICT, added from the list's own data
+    pub skipped: usize,     // Entries with no usable reading
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::data::models::DictEntry;
+
+// Subtitle import errors
+#[derive(Error, Debug)]
+pub enum SubtitleError {
+    #[error("Unsupported subtitle format, expected SRT, ASS/SSA or WebVTT")]
+    UnsupportedFormat,
+    #[error("Subtitle file must be UTF-8 or UTF-16 encoded")]
+    InvalidEncoding,
+    #[error("Invalid timestamp on line {0}")]
+    InvalidTimestamp(usize),
+    #[error("No subtitle lines found")]
+    Empty,
+}
+
+/// A single timed line of dialogue from a subtitle file
+#[derive(Debug, Clone)]
+pub struct SubtitleLine {
+    pub start_ms: i32,   // Start time in milliseconds
+    pub text: String,    // Dialogue with formatting tags removed
+}
+
+/// Where an imported word was first seen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordContext {
+    pub line: String,                 // Full line the word appeared in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,       // Name of the file the line came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp_ms: Option<i32>,    // Start time of the line
+}
+
+impl WordContext {
+    /// Rebuilds a context from the nullable `deck_words` columns
+    pub fn from_columns(line: Option<String>, source: Option<String>, timestamp_ms: Option<i32>) -> Option<Self> {
+        line.map(|line| WordContext { line, source, timestamp_ms })
+    }
+}
+
+/// A word found in a subtitle file, ranked by how often it occurs
+#[derive(Debug, Serialize)]
+pub struct MinedWord {
+    pub word: String,             // Word as written in the file
+    pub frequency: u32,           // Occurrences in the file
+    pub entries: Vec<DictEntry>,  // Candidate dictionary entries
+    pub context: WordContext,     // First line the word appeared in
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubtitlePreview {
+    pub source: String,       // Uploaded file name
+    pub line_count: usize,    // Dialogue lines parsed
+    pub words: Vec<MinedWord>,
+}
+
+/// Request payload for creating a deck from selected subtitle words
+#[derive(Deserialize)]
+pub struct SubtitleDeckRequest {
+    pub name: String,
+    #[serde(default)]
+    pub privacy_value: bool,
+    pub words: Vec<SubtitleWordSelection>,
+}
+
+#[derive(Deserialize)]
+pub struct SubtitleWordSelection {
+    pub word_data: serde_json::Value,   // Same shape as AddWordRequest::word_data
+    pub context: Option<WordContext>,
+}
+
+/// Result of a bulk import into a new deck
+#[derive(Serialize)]
+pub struct ImportResult {
+    pub deck_id: i32,
+    pub imported: usize,   // Words added to the deck
+    pub skipped: usize,    // Duplicates that were already in the deck
+}
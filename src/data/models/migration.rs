@@ -0,0 +1,32 @@
+use thiserror::Error;
+
+// Database migration errors
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Connection error: {0}")]
+    Pool(#[from] diesel::r2d2::PoolError),
+    #[error("Database error: {0}")]
+    Database(#[from] diesel::result::Error),
+    #[error("Migration failed: {0}")]
+    Failed(Box<dyn std::error::Error + Send + Sync>),
+    #[error(
+        "The database has tables but no migration history. Back it up and record the \
+         migrations it already matches in __diesel_schema_migrations before starting"
+    )]
+    Untracked,
+    #[error("The database was migrated by a newer version, unknown migrations: {}", .0.join(", "))]
+    Unknown(Vec<String>),
+    #[error(
+        "The database is partially migrated: {} not applied while later migrations are",
+        .0.join(", ")
+    )]
+    OutOfOrder(Vec<String>),
+    #[error("The database has pending migrations ({}), run the `migrate` command", .0.join(", "))]
+    Pending(Vec<String>),
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for MigrationError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        MigrationError::Failed(error)
+    }
+}
@@ -1,13 +1,31 @@
+pub mod anki;
 pub mod auth;
+pub mod config;
 pub mod deck;
+pub mod error;
+pub mod hsk;
+pub mod import;
+pub mod migration;
 pub mod parser;
+pub mod pinyin;
 pub mod search;
 pub mod srs;
+pub mod tag;
 pub mod user;
+pub mod wordlist;
 
+pub use anki::*;
 pub use auth::*;
+pub use config::*;
 pub use deck::*;
+pub use error::*;
+pub use hsk::*;
+pub use import::*;
+pub use migration::*;
 pub use parser::*;
+pub use pinyin::*;
 pub use search::*;
 pub use srs::*;
+pub use tag::*;
 pub use user::*;
+pub use wordlist::*;
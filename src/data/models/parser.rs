@@ -1,4 +1,18 @@
 use serde::{Serialize, Deserialize};
+use std::path::PathBuf;
+use thiserror::Error;
+
+// Dictionary loading errors
+#[derive(Error, Debug)]
+pub enum DictionaryError {
+    #[error("Failed to read dictionary {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Dictionary {path} has no valid entries")]
+    Empty { path: PathBuf },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DictEntry {
@@ -6,4 +20,48 @@ pub struct DictEntry {
     pub simplified: String,
     pub pinyin: String,
     pub definitions: Vec<String>,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub classifiers: Vec<EntryRef>, // Measure words from `CL:` definitions
+    #[serde(default)]
+    pub variant_of: Vec<VariantRef>, // Entries this one is a variant of
+    #[serde(default)]
+    pub see_also: Vec<EntryRef>, // Entries named by `see` and `see also`
+    #[serde(default)]
+    pub abbreviation_of: Vec<EntryRef>, // Entries named by `abbr. for`
+    #[serde(default)]
+    pub surname: bool, // One of the definitions is a surname
+}
+
+/// Another entry named in a definition, such as `個|个[ge4]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryRef {
+    pub traditional: String,
+    pub simplified: String, // Same as `traditional` when the definition gives one form
+    pub pinyin: Option<String>,
+}
+
+/// An entry this one is a variant of, such as `old variant of 說|说[shuo1]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariantRef {
+    pub kind: String, // "variant", "old variant", "erhua variant" and so on
+    #[serde(flatten)]
+    pub entry: EntryRef,
+}
+
+/// Header metadata and load statistics of a CC-CEDICT file
+#[derive(Debug, Clone, Serialize)]
+pub struct DictionaryInfo {
+    pub path: PathBuf,
+    pub version: Option<String>,         // `#! version=` and `#! subversion=`, such as "1.0"
+    pub date: Option<String>,            // `#! date=`, when the file was published
+    pub declared_entries: Option<usize>, // `#! entries=`, as stated by the publisher
+    pub entries: usize,                  // Entries actually loaded
+    pub malformed: Vec<MalformedLine>,   // Lines skipped because they could not be parsed
+}
+
+/// A line of a CC-CEDICT file that could not be parsed
+#[derive(Debug, Clone, Serialize)]
+pub struct MalformedLine {
+    pub line: usize, // 1-based line number
+    pub reason: &'static str,
+}
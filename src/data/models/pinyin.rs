@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+/// How pinyin is written in API responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PinyinFormat {
+    Numbers, // Tone numbers as in CC-CEDICT, such as `ni3 hao3`
+    Marks,   // Tone marks, such as `nǐ hǎo`
+    Zhuyin,  // Bopomofo, such as `ㄋㄧˇ ㄏㄠˇ`
+}
+
+/// Query parameter choosing the pinyin format, stored pinyin is returned when omitted
+#[derive(Debug, Deserialize)]
+pub struct PinyinQuery {
+    pub pinyin: Option<PinyinFormat>,
+}
+
+/// A single pinyin syllable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Syllable {
+    pub letters: String,  // Toneless spelling with `ü`, keeping the original case
+    pub tone: Option<u8>, // 1-4, or 5 for the neutral tone. `None` when not written
+}
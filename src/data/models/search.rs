@@ -4,11 +4,47 @@ use crate::data::models::DictEntry;
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
     pub q: String,
-    pub lang: Option<String>,
+    pub lang: Option<String>, // "chinese", "english", or "auto" (the default) to detect it
+}
+
+/// How a search query was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryLanguage {
+    Hanzi,
+    Pinyin,
+    English,
+}
+
+/// One reading of a query and the weight applied to the scores it produced
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryInterpretation {
+    pub language: QueryLanguage,
+    pub weight: f32,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SearchResult {
     pub query: String,
+    pub interpretations: Vec<QueryInterpretation>, // Readings that were searched, strongest first
     pub results: Vec<DictEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SegmentParams {
+    pub q: String,
+}
+
+/// A word (or run of unknown characters) found by the segmenter
+#[derive(Debug, Serialize)]
+pub struct SegmentToken {
+    pub text: String,
+    pub start: usize,             // Character offset in the original text
+    pub entries: Vec<DictEntry>,  // Dictionary entries for this word, empty if unknown
+}
+
+#[derive(Debug, Serialize)]
+pub struct SegmentResult {
+    pub query: String,
+    pub tokens: Vec<SegmentToken>,
 }
\ No newline at end of file
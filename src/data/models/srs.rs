@@ -1,7 +1,8 @@
 use chrono::NaiveDateTime;
-use diesel::{Insertable, Queryable};
+use diesel::{AsChangeset, Insertable, Queryable};
+use serde::{Deserialize, Serialize};
 
-use crate::schema::srs_reviews;
+use crate::schema::{review_log, srs_reviews, user_settings};
 
 /// Represents a review record in the SRS system
 #[derive(Queryable, Insertable, Debug)]
@@ -16,4 +17,96 @@ pub struct SrsReview {
     pub ease_factor: f32,
     pub interval: i32,
     pub performance: i32,
+    pub stability: Option<f32>,  // FSRS memory stability in days (FSRS only)
+    pub difficulty: Option<f32>, // FSRS difficulty between 1 and 10 (FSRS only)
+}
+
+/// An append-only record of a single review, kept for statistics
+#[derive(Insertable, Debug)]
+#[diesel(table_name = review_log)]
+pub struct NewReviewLog {
+    pub user_id: i32,
+    pub word_id: i32,
+    pub deck_id: i32,
+    pub reviewed_at: NaiveDateTime,
+    pub rating: i32,
+    pub previous_interval: Option<i32>,
+    pub new_interval: i32,
+    pub previous_ease: Option<f32>,
+    pub new_ease: f32,
+    pub elapsed_days: Option<i32>,
+}
+
+/// Scheduling algorithm used for a user's reviews
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerKind {
+    Sm2,
+    Fsrs,
+}
+
+impl SchedulerKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SchedulerKind::Sm2 => "sm2",
+            SchedulerKind::Fsrs => "fsrs",
+        }
+    }
+
+    /// Parses the value stored in the database, falling back to SM-2
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "fsrs" => SchedulerKind::Fsrs,
+            _ => SchedulerKind::Sm2,
+        }
+    }
+}
+
+/// Per-user SRS preferences
+#[derive(Queryable, Insertable, AsChangeset, Debug)]
+#[diesel(table_name = user_settings)]
+pub struct UserSettings {
+    pub user_id: i32,
+    pub scheduler: String,      // "sm2" or "fsrs"
+    pub desired_retention: f32, // Target recall probability used by FSRS
+}
+
+impl UserSettings {
+    pub const DEFAULT_DESIRED_RETENTION: f32 = 0.9;
+
+    /// Settings used for users who never changed them
+    pub fn defaults(user_id: i32) -> Self {
+        UserSettings {
+            user_id,
+            scheduler: SchedulerKind::Sm2.as_str().to_string(),
+            desired_retention: Self::DEFAULT_DESIRED_RETENTION,
+        }
+    }
+
+    pub fn scheduler_kind(&self) -> SchedulerKind {
+        SchedulerKind::from_db(&self.scheduler)
+    }
+}
+
+/// SRS settings as exposed by the API
+#[derive(Serialize)]
+pub struct SrsSettings {
+    pub scheduler: SchedulerKind,
+    pub desired_retention: f32,
+}
+
+impl From<UserSettings> for SrsSettings {
+    fn from(settings: UserSettings) -> Self {
+        SrsSettings {
+            scheduler: settings.scheduler_kind(),
+            desired_retention: settings.desired_retention,
+        }
+    }
+}
+
+/// Request payload for updating SRS settings, omitted fields are left unchanged
+#[derive(Deserialize)]
+pub struct UpdateSrsSettingsRequest {
+    pub scheduler: Option<SchedulerKind>,
+    pub desired_retention: Option<f32>,
 }
\ No newline at end of file
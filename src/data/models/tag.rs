@@ -0,0 +1,50 @@
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Longest tag accepted, in characters
+pub const MAX_TAG_LENGTH: usize = 32;
+/// Most tags a single deck can carry
+pub const MAX_TAGS_PER_DECK: usize = 10;
+
+// Deck tag errors
+#[derive(Error, Debug)]
+pub enum DeckTagError {
+    #[error("Tags cannot be empty")]
+    Empty,
+    #[error("Tags cannot contain commas")]
+    Comma,
+    #[error("Tags cannot contain <, >, &, \" or '")]
+    Markup,
+    #[error("Tags can be at most {MAX_TAG_LENGTH} characters long")]
+    TooLong,
+    #[error("A deck can have at most {MAX_TAGS_PER_DECK} tags")]
+    TooMany,
+}
+
+/// Request payload for adding tags to a deck
+#[derive(Deserialize)]
+pub struct DeckTagsRequest {
+    pub tags: Vec<String>, // Normalized before they are stored
+}
+
+/// Tags of a deck after a change
+#[derive(Serialize)]
+pub struct DeckTags {
+    pub deck_id: i32,
+    pub tags: Vec<String>, // Sorted alphabetically
+}
+
+/// Query parameters for tag autocompletion
+#[derive(Deserialize)]
+pub struct TagSuggestQuery {
+    pub q: Option<String>,   // Prefix of the tag
+    pub limit: Option<i64>,  // Defaults to 10, at most 50
+}
+
+/// A tag and how many visible decks use it
+#[derive(Serialize, Queryable)]
+pub struct TagCount {
+    pub tag: String,
+    pub deck_count: i64,
+}
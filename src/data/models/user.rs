@@ -10,6 +10,7 @@ pub struct User {
     pub user_id: i32,
     pub email: String,
     pub password: String,
+    pub is_system: bool, // Owned by the application, cannot log in
 }
 
 #[derive(Insertable)]
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// Word list (CSV/TSV) import errors
+#[derive(Error, Debug)]
+pub enum WordListError {
+    #[error("Word list must be UTF-8 or UTF-16 encoded")]
+    InvalidEncoding,
+    #[error("Invalid word list: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("No rows found in word list")]
+    Empty,
+    #[error("Column {0} is out of range")]
+    InvalidColumn(usize),
+}
+
+/// A non-empty row of an uploaded word list
+#[derive(Debug, Clone)]
+pub struct WordListRow {
+    pub line: usize,         // One-based line number in the file
+    pub cells: Vec<String>,  // Trimmed cell values
+}
+
+/// First rows of an uploaded word list, shown before choosing a column mapping
+#[derive(Debug, Serialize)]
+pub struct WordListPreview {
+    pub delimiter: String,      // Detected delimiter, "," or "\t"
+    pub column_count: usize,    // Widest row in the file
+    pub row_count: usize,
+    pub rows: Vec<Vec<String>>, // First rows, including any header
+}
+
+/// Zero-based columns holding each part of a word. Only `simplified` is
+/// required: hanzi-only and hanzi+pinyin lists are completed from the dictionary.
+#[derive(Deserialize)]
+pub struct WordListColumns {
+    pub simplified: usize,
+    pub traditional: Option<usize>,
+    pub pinyin: Option<usize>,
+    pub definition: Option<usize>,
+}
+
+/// Options sent alongside the uploaded word list, as the `options` form field
+#[derive(Deserialize)]
+pub struct WordListImportOptions {
+    pub deck_id: Option<i32>,  // Existing deck to add to
+    pub name: Option<String>,  // Name of a new deck, when no deck_id is given
+    #[serde(default)]
+    pub privacy_value: bool,
+    #[serde(default)]
+    pub has_header: bool,      // Skip the first row
+    pub delimiter: Option<char>, // Overrides the detected delimiter
+    pub columns: WordListColumns,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RowErrorKind {
+    MissingField,     // Empty hanzi column
+    UnknownWord,      // Not in the dictionary and not enough columns to add it as is
+    AmbiguousReading, // Several dictionary readings and no pinyin to choose one
+    Duplicate,        // Already in the deck or earlier in the file
+}
+
+/// A row that was not imported
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    pub row: usize,  // One-based row number in the file
+    pub kind: RowErrorKind,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub readings: Vec<String>, // Candidate readings for ambiguous rows
+}
+
+#[derive(Serialize)]
+pub struct WordListImportResult {
+    pub deck_id: i32,
+    pub imported: usize,
+    pub errors: Vec<RowError>,
+}
@@ -1,38 +1,147 @@
 use std::fs;
-use crate::data::models::DictEntry;
+use std::path::Path;
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::data::models::{DictEntry, DictionaryError, DictionaryInfo, EntryRef, MalformedLine, VariantRef};
 
-pub fn parse_cedict() -> Vec<DictEntry> {
+lazy_static! {
+    // `傳統|传统[chuan2 tong3]`, `位[wei4]` or a bare `說`
+    static ref REF_RE: Regex = Regex::new(r"^(\p{Han}[^\s|\[\],;/]*)(?:\|([^\s|\[\],;/]+))?(?:\[([^\]]*)\])?").unwrap();
+    static ref VARIANT_RE: Regex = Regex::new(r"^((?:[\w()-]+ ){0,3}?variant) of (.+)$").unwrap();
+}
 
-    let content = fs::read_to_string("src/data/cedict_ts.u8")
-        .expect("Errore nella lettura del file cedict_ts.u8");
+/// Reads a CC-CEDICT file. Malformed lines are skipped and reported in the
+/// returned `DictionaryInfo` with their line numbers.
+pub fn parse_cedict(path: &Path) -> Result<(Vec<DictEntry>, DictionaryInfo), DictionaryError> {
+    let content = fs::read_to_string(path).map_err(|source| DictionaryError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
 
     let mut entries = Vec::new();
+    let mut info = DictionaryInfo {
+        path: path.to_path_buf(),
+        version: None,
+        date: None,
+        declared_entries: None,
+        entries: 0,
+        malformed: Vec::new(),
+    };
+    let mut subversion = None;
 
-    for line in content.lines() {
+    for (number, line) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(header) = line.strip_prefix("#!") {
+            match header.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+                Some(("version", value)) => info.version = Some(value.to_string()),
+                Some(("subversion", value)) => subversion = Some(value.to_string()),
+                Some(("date", value)) => info.date = Some(value.to_string()),
+                Some(("entries", value)) => info.declared_entries = value.parse().ok(),
+                _ => {}
+            }
+            continue;
+        }
         if line.starts_with('#') || line.is_empty() {
             continue;
         }
 
-        let parts: Vec<_> = line.split(" /").collect();
-        if parts.len() < 2 {
-            continue;
+        match parse_line(line) {
+            Ok(mut entry) => {
+                annotate(&mut entry);
+                entries.push(entry);
+            }
+            Err(reason) => info.malformed.push(MalformedLine { line: number + 1, reason }),
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(DictionaryError::Empty { path: path.to_path_buf() });
+    }
+    if let (Some(version), Some(subversion)) = (&mut info.version, subversion) {
+        *version = format!("{}.{}", version, subversion);
+    }
+    info.entries = entries.len();
+
+    Ok((entries, info))
+}
+
+/// Parses `TRADITIONAL SIMPLIFIED [pin1 yin1] /definition/.../`
+fn parse_line(line: &str) -> Result<DictEntry, &'static str> {
+    let (head, definitions) = line.split_once(" /").ok_or("missing definitions")?;
+    let (forms, pinyin) = head.split_once('[').ok_or("missing pinyin")?;
+    let pinyin = pinyin.trim_end().strip_suffix(']').ok_or("unterminated pinyin")?.trim();
+    if pinyin.is_empty() {
+        return Err("empty pinyin");
+    }
+
+    let mut forms = forms.split_whitespace();
+    let (Some(traditional), Some(simplified), None) = (forms.next(), forms.next(), forms.next()) else {
+        return Err("expected traditional and simplified forms");
+    };
+
+    let definitions: Vec<String> = definitions
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    if definitions.is_empty() {
+        return Err("missing definitions");
+    }
+
+    Ok(DictEntry {
+        traditional: traditional.to_string(),
+        simplified: simplified.to_string(),
+        pinyin: pinyin.to_string(),
+        definitions,
+        classifiers: Vec::new(),
+        variant_of: Vec::new(),
+        see_also: Vec::new(),
+        abbreviation_of: Vec::new(),
+        surname: false,
+    })
+}
+
+/// Fills the structured fields from the CC-CEDICT conventions used in the definitions
+fn annotate(entry: &mut DictEntry) {
+    for definition in &entry.definitions {
+        if let Some(rest) = definition.strip_prefix("CL:") {
+            entry.classifiers.extend(parse_refs(rest));
+        } else if let Some(rest) = definition.strip_prefix("see also ").or_else(|| definition.strip_prefix("see ")) {
+            entry.see_also.extend(parse_refs(rest));
+        } else if let Some(rest) = definition.strip_prefix("abbr. for ") {
+            entry.abbreviation_of.extend(parse_refs(rest));
+        } else if definition == "surname" || definition.starts_with("surname ") {
+            entry.surname = true;
+        } else if let Some(caps) = VARIANT_RE.captures(definition) {
+            entry.variant_of.extend(parse_refs(&caps[2]).into_iter().map(|target| VariantRef {
+                kind: caps[1].to_string(),
+                entry: target,
+            }));
         }
+    }
+}
+
+/// Reads the references at the start of `text`, separated by commas or "and",
+/// stopping at the first thing that is not a reference
+fn parse_refs(text: &str) -> Vec<EntryRef> {
+    let mut refs = Vec::new();
+    let mut rest = text.trim_start();
 
-        let chars_pinyin: Vec<_> = parts[0].split('[').collect();
-        let chars: Vec<_> = chars_pinyin[0].split_whitespace().collect();
-
-        entries.push(DictEntry {
-            traditional: chars[0].to_string(),
-            simplified: chars[1].to_string(),
-            pinyin: chars_pinyin[1].trim_end_matches(']').to_string(),
-            definitions: parts[1..].iter()
-                .flat_map(|s| s.split('/'))
-                .map(str::trim)
-                .filter(|s| !s.is_empty())
-                .map(str::to_string)
-                .collect(),
+    while let Some(caps) = REF_RE.captures(rest) {
+        let traditional = caps[1].to_string();
+        refs.push(EntryRef {
+            simplified: caps.get(2).map_or_else(|| traditional.clone(), |m| m.as_str().to_string()),
+            pinyin: caps.get(3).map(|m| m.as_str().trim().to_string()).filter(|p| !p.is_empty()),
+            traditional,
         });
+
+        rest = &rest[caps[0].len()..];
+        match [",", " and ", " or "].iter().find_map(|sep| rest.strip_prefix(sep)) {
+            Some(next) => rest = next.trim_start(),
+            None => break,
+        }
     }
 
-    entries
-}
\ No newline at end of file
+    refs
+}
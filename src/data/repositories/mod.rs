@@ -1,3 +1,5 @@
+pub mod session_store;
 pub mod user;
 
+pub use session_store::*;
 pub use user::*;
\ No newline at end of file
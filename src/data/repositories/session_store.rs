@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use time::OffsetDateTime;
+use tower_sessions::session::{Id, Record};
+use tower_sessions::session_store::{self, ExpiredDeletion, SessionStore};
+
+use crate::DbPool;
+use crate::schema::sessions;
+
+/// Session store persisting tower-sessions records in the `sessions` table,
+/// so logins survive restarts
+#[derive(Clone, Debug)]
+pub struct SqliteSessionStore {
+    pool: DbPool,
+}
+
+impl SqliteSessionStore {
+    pub fn new(pool: DbPool) -> Self {
+        SqliteSessionStore { pool }
+    }
+
+    /// Deletes expired sessions every `period`, forever. Meant to be spawned as a task
+    pub async fn continuously_delete_expired(self, period: std::time::Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.delete_expired().await {
+                log::error!("Failed to delete expired sessions: {}", e);
+            }
+        }
+    }
+
+    /// Runs a blocking diesel operation on a pooled connection off the async runtime
+    async fn with_conn<T, F>(&self, f: F) -> session_store::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut SqliteConnection) -> Result<T, diesel::result::Error> + Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(backend_error)?;
+            f(&mut conn).map_err(backend_error)
+        })
+        .await
+        .map_err(backend_error)?
+    }
+}
+
+#[async_trait]
+impl SessionStore for SqliteSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        // Pick a new ID until it does not collide with an existing session
+        loop {
+            let (id, data, expires_at) = encode(record)?;
+            let inserted = self
+                .with_conn(move |conn| {
+                    diesel::insert_into(sessions::table)
+                        .values((
+                            sessions::id.eq(id),
+                            sessions::data.eq(data),
+                            sessions::expires_at.eq(expires_at),
+                        ))
+                        .on_conflict_do_nothing()
+                        .execute(conn)
+                })
+                .await?;
+
+            if inserted > 0 {
+                return Ok(());
+            }
+            record.id = Id::default();
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        let (id, data, expires_at) = encode(record)?;
+        self.with_conn(move |conn| {
+            diesel::insert_into(sessions::table)
+                .values((
+                    sessions::id.eq(&id),
+                    sessions::data.eq(&data),
+                    sessions::expires_at.eq(expires_at),
+                ))
+                .on_conflict(sessions::id)
+                .do_update()
+                .set((
+                    sessions::data.eq(&data),
+                    sessions::expires_at.eq(expires_at),
+                ))
+                .execute(conn)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        let id = session_id.to_string();
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        let data = self
+            .with_conn(move |conn| {
+                sessions::table
+                    .filter(sessions::id.eq(id))
+                    .filter(sessions::expires_at.gt(now))
+                    .select(sessions::data)
+                    .first::<String>(conn)
+                    .optional()
+            })
+            .await?;
+
+        data.map(|data| {
+            serde_json::from_str(&data).map_err(|e| session_store::Error::Decode(e.to_string()))
+        })
+        .transpose()
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        let id = session_id.to_string();
+        self.with_conn(move |conn| {
+            diesel::delete(sessions::table.filter(sessions::id.eq(id))).execute(conn)
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExpiredDeletion for SqliteSessionStore {
+    async fn delete_expired(&self) -> session_store::Result<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let deleted = self
+            .with_conn(move |conn| {
+                diesel::delete(sessions::table.filter(sessions::expires_at.le(now))).execute(conn)
+            })
+            .await?;
+
+        if deleted > 0 {
+            log::info!("Deleted {} expired sessions", deleted);
+        }
+        Ok(())
+    }
+}
+
+/// Splits a record into its `sessions` row values
+fn encode(record: &Record) -> session_store::Result<(String, String, i64)> {
+    let data = serde_json::to_string(record).map_err(|e| session_store::Error::Encode(e.to_string()))?;
+    Ok((record.id.to_string(), data, record.expiry_date.unix_timestamp()))
+}
+
+fn backend_error(e: impl std::fmt::Display) -> session_store::Error {
+    session_store::Error::Backend(e.to_string())
+}
@@ -1,5 +1,21 @@
 ﻿// @generated automatically by Diesel CLI.
 
+diesel::table! {
+    deck_likes (user_id, deck_id) {
+        user_id -> Integer,
+        deck_id -> Integer,
+        liked_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    deck_subscriptions (user_id, deck_id) {
+        user_id -> Integer,
+        deck_id -> Integer,
+        subscribed_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     deck_tags (deck_id, tag) {
         deck_id -> Integer,
@@ -11,6 +27,14 @@ diesel::table! {
     deck_words (deck_id, word_id) {
         deck_id -> Integer,
         word_id -> Integer,
+        context_line -> Nullable<Text>,
+        context_source -> Nullable<Text>,
+        context_timestamp_ms -> Nullable<Integer>,
+        position -> Integer,
+        custom_definition -> Nullable<Text>,
+        note -> Nullable<Text>,
+        mnemonic -> Nullable<Text>,
+        example -> Nullable<Text>,
     }
 }
 
@@ -23,6 +47,34 @@ diesel::table! {
         user_made -> Bool,
         duplicated_check -> Bool,
         like_count -> Integer,
+        scheduler -> Nullable<Text>,
+        source_deck_id -> Nullable<Integer>,
+        description -> Nullable<Text>,
+        cover_word_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    review_log (log_id) {
+        log_id -> Integer,
+        user_id -> Integer,
+        word_id -> Integer,
+        deck_id -> Integer,
+        reviewed_at -> Timestamp,
+        rating -> Integer,
+        previous_interval -> Nullable<Integer>,
+        new_interval -> Integer,
+        previous_ease -> Nullable<Float>,
+        new_ease -> Float,
+        elapsed_days -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    sessions (id) {
+        id -> Text,
+        data -> Text,
+        expires_at -> BigInt,
     }
 }
 
@@ -37,6 +89,16 @@ diesel::table! {
         ease_factor -> Float,
         interval -> Integer,
         performance -> Integer,
+        stability -> Nullable<Float>,
+        difficulty -> Nullable<Float>,
+    }
+}
+
+diesel::table! {
+    user_settings (user_id) {
+        user_id -> Integer,
+        scheduler -> Text,
+        desired_retention -> Float,
     }
 }
 
@@ -45,6 +107,7 @@ diesel::table! {
         user_id -> Integer,
         email -> Text,
         password -> Text,
+        is_system -> Bool,
     }
 }
 
@@ -59,19 +122,33 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(deck_likes -> decks (deck_id));
+diesel::joinable!(deck_likes -> users (user_id));
+diesel::joinable!(deck_subscriptions -> decks (deck_id));
+diesel::joinable!(deck_subscriptions -> users (user_id));
 diesel::joinable!(deck_tags -> decks (deck_id));
 diesel::joinable!(deck_words -> decks (deck_id));
 diesel::joinable!(deck_words -> words (word_id));
 diesel::joinable!(decks -> users (user_id));
+diesel::joinable!(decks -> words (cover_word_id));
+diesel::joinable!(review_log -> decks (deck_id));
+diesel::joinable!(review_log -> users (user_id));
+diesel::joinable!(review_log -> words (word_id));
 diesel::joinable!(srs_reviews -> decks (deck_id));
 diesel::joinable!(srs_reviews -> users (user_id));
 diesel::joinable!(srs_reviews -> words (word_id));
+diesel::joinable!(user_settings -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    deck_likes,
+    deck_subscriptions,
     deck_tags,
     deck_words,
     decks,
+    review_log,
+    sessions,
     srs_reviews,
+    user_settings,
     users,
     words,
 );
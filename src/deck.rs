@@ -1,45 +1,72 @@
 use axum::extract::Path;
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    extract::{Json, Query, State},
+    response::Response,
 };
+use std::collections::{HashMap, HashSet};
 use diesel::prelude::*;
 use diesel::sql_types::Integer;
 use chrono::{NaiveDateTime, Utc};
 use crate::{
     DbPool,
-    data::schema::{deck_words, decks, words, srs_reviews},
-    data::models::{Deck, DeckWithWords, DeckWord, DeckId, CreateDeckRequest, AddWordRequest, ApiResponse, Word, StudyWord, ReviewRequest, UpdatePrivacyRequest},
-    utils,
+    data::schema::{deck_likes, deck_subscriptions, deck_tags, deck_words, decks, review_log, words, srs_reviews},
+    data::models::{AppError, AnkiExportNote, AnkiExportParams, AnkiExportSchedule, Deck, DeckListQuery, DeckWithWords, DeckWord, DeckWordNotes, DeckId, PinyinQuery, CreateDeckRequest, AddWordRequest, ApiResponse, Word, StudyWord, ReorderDeckRequest, ReviewRequest, StudyOrder, StudySessionQuery, UpdateDeckRequest, UpdateDeckSchedulerRequest, UpdatePrivacyRequest, WordContext},
+    utils::{self, CurrentUser, OwnedDeck},
+    handlers::decks::tags,
+    features::anki::AnkiExporter,
     features::srs::SrsEngine 
 };
 
-/// Lists all decks for the current user
+/// Longest deck name accepted when renaming, in characters
+const MAX_DECK_NAME_LENGTH: usize = 100;
+/// Longest deck description accepted, in characters
+const MAX_DECK_DESCRIPTION_LENGTH: usize = 2000;
+/// Longest deck-specific word note accepted, in characters
+const MAX_WORD_NOTE_LENGTH: usize = 2000;
+
+/// Lists all decks for the current user, optionally only those with all of the given tags
 pub async fn list_decks(
+    Query(params): Query<DeckListQuery>,
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
-) -> Result<Json<Vec<Deck>>, (StatusCode, String)> {
-    // Get current user ID from session
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<Deck>>, AppError> {
     // Get database connection
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
+    let mut conn = pool.get()?;
 
     // Query all decks belonging to this user
-    let decks = decks::table
+    let mut query = decks::table
         .filter(decks::user_id.eq(user_id))
         .select((decks::deck_id, decks::deck_name, decks::privacy_value))
-        .load::<(i32, String, bool)>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?
+        .into_boxed();
+    for tag in tags::parse_tag_filter(params.tags.as_deref()) {
+        let tagged = deck_tags::table
+            .filter(deck_tags::tag.eq(tag))
+            .select(deck_tags::deck_id);
+        query = query.filter(decks::deck_id.eq_any(tagged));
+    }
+
+    let rows = query
+        .load::<(i32, String, bool)>(&mut conn)?;
+
+    // Tags of the listed decks
+    let deck_ids: Vec<i32> = rows.iter().map(|row| row.0).collect();
+    let mut deck_tags: HashMap<i32, Vec<String>> = HashMap::new();
+    for (deck_id, tag) in deck_tags::table
+        .filter(deck_tags::deck_id.eq_any(&deck_ids))
+        .order(deck_tags::tag.asc())
+        .load::<(i32, String)>(&mut conn)?
+    {
+        deck_tags.entry(deck_id).or_default().push(tag);
+    }
+
+    let decks = rows
         .into_iter()
-        .map(|(id, name, privacy_value)| Deck { id, name, privacy_value })
+        .map(|(id, name, privacy_value)| Deck {
+            id,
+            name,
+            privacy_value,
+            tags: deck_tags.remove(&id).unwrap_or_default(),
+        })
         .collect();
 
     Ok(Json(decks))
@@ -48,33 +75,15 @@ pub async fn list_decks(
 /// Creates a new deck with optional initial word
 pub async fn create_deck(
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
+    CurrentUser(user_id): CurrentUser,
     Json(payload): Json<CreateDeckRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
-    // Verify user is logged in
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
 
     // Use transaction to ensure atomicity
     conn.transaction::<_, diesel::result::Error, _>(|conn| {
         // Create the new deck
-        diesel::insert_into(decks::table)
-            .values((
-                decks::deck_name.eq(&payload.name),
-                decks::user_id.eq(user_id),
-                decks::privacy_value.eq(payload.privacy_value),
-            ))
-            .execute(conn)?;
-
-        // Get the auto-generated deck ID
-        let deck_id = diesel::select(diesel::dsl::sql::<Integer>("last_insert_rowid()"))
-            .get_result::<i32>(conn)?;
+        let deck_id = create_deck_internal(conn, user_id, &payload.name, payload.privacy_value)?;
 
         // Add initial word if provided
         if let Some(word_data) = payload.word_data {
@@ -82,9 +91,6 @@ pub async fn create_deck(
         }
 
         Ok(())
-    })
-    .map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
     })?;
 
     Ok(Json(ApiResponse {
@@ -93,37 +99,36 @@ pub async fn create_deck(
     }))
 }
 
+/// Internal function to create an empty deck for a user
+/// Returns the auto-generated deck ID
+pub(crate) fn create_deck_internal(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    name: &str,
+    privacy_value: bool,
+) -> Result<i32, diesel::result::Error> {
+    diesel::insert_into(decks::table)
+        .values((
+            decks::deck_name.eq(name),
+            decks::user_id.eq(user_id),
+            decks::privacy_value.eq(privacy_value),
+        ))
+        .execute(conn)?;
+
+    diesel::select(diesel::dsl::sql::<Integer>("last_insert_rowid()"))
+        .get_result::<i32>(conn)
+}
+
 /// Adds a word to an existing deck
 pub async fn add_word_to_deck(
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
+    CurrentUser(user_id): CurrentUser,
     Json(payload): Json<AddWordRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
-    // Verify user is logged in
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
 
     // Verify the user owns the deck they're trying to modify
-    let deck_owner: i32 = decks::table
-        .filter(decks::deck_id.eq(payload.deck_id))
-        .select(decks::user_id)
-        .first(&mut conn)
-        .map_err(|e| match e {
-            diesel::result::Error::NotFound => {
-                (StatusCode::NOT_FOUND, "Deck not found".to_string())
-            }
-            _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)),
-        })?;
-
-    if deck_owner != user_id {
-        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
-    }
+    utils::verify_deck_owner(&mut conn, payload.deck_id, user_id)?;
 
     // Add the word to the deck
     match add_word_to_deck_internal(&mut conn, payload.deck_id, payload.word_data) {
@@ -134,17 +139,14 @@ pub async fn add_word_to_deck(
         Err(diesel::result::Error::DatabaseError(
             diesel::result::DatabaseErrorKind::UniqueViolation,
             _,
-        )) => Err((StatusCode::CONFLICT, "Word already exists in this deck".to_string())),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Database error: {}", e),
-        )),
+        )) => Err(AppError::Conflict("Word already exists in this deck".to_string())),
+        Err(e) => Err(e.into()),
     }
 }
 
 /// Internal function to handle word addition to a deck
 /// Returns the auto-generated word ID
-fn add_word_to_deck_internal(
+pub(crate) fn add_word_to_deck_internal(
     conn: &mut SqliteConnection,
     deck_id: i32,
     word_data: serde_json::Value,
@@ -220,58 +222,68 @@ fn add_word_to_deck_internal(
         ));
     }
 
+    // New words go to the end of the deck
+    let position = deck_words::table
+        .filter(deck_words::deck_id.eq(deck_id))
+        .select(diesel::dsl::max(deck_words::position))
+        .first::<Option<i32>>(conn)?
+        .map_or(0, |last| last + 1);
+
     // Add to deck_words
     diesel::insert_into(deck_words::table)
         .values((
             deck_words::deck_id.eq(deck_id),
             deck_words::word_id.eq(word_id),
+            deck_words::position.eq(position),
         ))
         .execute(conn)?;
 
     Ok(word_id)
 }
 
+/// Internal function to attach the context a word was found in to its deck entry
+pub(crate) fn set_word_context(
+    conn: &mut SqliteConnection,
+    deck_id: i32,
+    word_id: i32,
+    context: &WordContext,
+) -> Result<(), diesel::result::Error> {
+    diesel::update(
+        deck_words::table
+            .filter(deck_words::deck_id.eq(deck_id))
+            .filter(deck_words::word_id.eq(word_id)),
+    )
+    .set((
+        deck_words::context_line.eq(&context.line),
+        deck_words::context_source.eq(&context.source),
+        deck_words::context_timestamp_ms.eq(context.timestamp_ms),
+    ))
+    .execute(conn)?;
+
+    Ok(())
+}
+
 /// Deletes a deck and all its words
 pub async fn delete_deck(
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
-    Path(deck_id): Path<i32>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
-    // Verify user is logged in
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
-
-    // Verify the deck exists and belongs to this user
-    let deck_exists = decks::table
-        .filter(decks::deck_id.eq(deck_id))
-        .filter(decks::user_id.eq(user_id))
-        .count()
-        .get_result::<i64>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })? > 0;
-
-    if !deck_exists {
-        return Err((StatusCode::NOT_FOUND, "Deck not found".to_string()));
-    }
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
 
     // Use transaction to atomically delete deck and its words
     conn.transaction::<_, diesel::result::Error, _>(|conn| {
         // First delete from junction table
         diesel::delete(deck_words::table.filter(deck_words::deck_id.eq(deck_id)))
             .execute(conn)?;
+        diesel::delete(deck_likes::table.filter(deck_likes::deck_id.eq(deck_id)))
+            .execute(conn)?;
+        diesel::delete(deck_subscriptions::table.filter(deck_subscriptions::deck_id.eq(deck_id)))
+            .execute(conn)?;
+        diesel::delete(deck_tags::table.filter(deck_tags::deck_id.eq(deck_id)))
+            .execute(conn)?;
         // Then delete the deck itself
         diesel::delete(decks::table.filter(decks::deck_id.eq(deck_id)))
             .execute(conn)
-    })
-    .map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
     })?;
 
     Ok(Json(ApiResponse {
@@ -283,42 +295,27 @@ pub async fn delete_deck(
 //Deletes a word from a deck
 pub async fn delete_word_from_deck(
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
-    Path((deck_id, word_id)): Path<(i32, i32)>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
-    // Verify user is logged in
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
-
-    // Verify the deck exists and belongs to this user
-    let deck_exists = decks::table
-        .filter(decks::deck_id.eq(deck_id))
-        .filter(decks::user_id.eq(user_id))
-        .count()
-        .get_result::<i64>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })? > 0;
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+    Path((_, word_id)): Path<(i32, i32)>,
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
 
-    if !deck_exists {
-        return Err((StatusCode::NOT_FOUND, "Deck not found".to_string()));
-    }
-
-    // Delete only the word-deck connection
-    diesel::delete(
-        deck_words::table
-            .filter(deck_words::deck_id.eq(deck_id))
-            .filter(deck_words::word_id.eq(word_id)),
-    )
-    .execute(&mut conn)
-    .map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        // Delete only the word-deck connection
+        diesel::delete(
+            deck_words::table
+                .filter(deck_words::deck_id.eq(deck_id))
+                .filter(deck_words::word_id.eq(word_id)),
+        )
+        .execute(conn)?;
+        // The word can no longer be the deck's cover
+        diesel::update(
+            decks::table
+                .filter(decks::deck_id.eq(deck_id))
+                .filter(decks::cover_word_id.eq(word_id)),
+        )
+        .set(decks::cover_word_id.eq(None::<i32>))
+        .execute(conn)
     })?;
 
     Ok(Json(ApiResponse {
@@ -329,31 +326,35 @@ pub async fn delete_word_from_deck(
 
 /// Views a deck with all its words
 pub async fn view_deck(
-    Path(deck_id): Path<i32>,
+    Query(display): Query<PinyinQuery>,
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
-) -> Result<Json<DeckWithWords>, (StatusCode, String)> {
-    // Verify user is logged in
-    let user_id = utils::get_current_user_id(&session)
-        .await
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Not logged in".to_string()))?;
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+) -> Result<Json<DeckWithWords>, AppError> {
+    let mut conn = pool.get()?;
 
     // Get basic deck info
-    let (id, name): (i32, String) = decks::table
+    let (id, name, description, cover_word_id): (i32, String, Option<String>, Option<i32>) = decks::table
         .filter(decks::deck_id.eq(deck_id))
-        .filter(decks::user_id.eq(user_id))
-        .select((decks::deck_id, decks::deck_name))
-        .first(&mut conn)
-        .map_err(|_| {
-            (StatusCode::NOT_FOUND, "Deck not found or access denied".to_string())
-        })?;
+        .select((decks::deck_id, decks::deck_name, decks::description, decks::cover_word_id))
+        .first(&mut conn)?;
 
     // Get all words in this deck
-    let words = deck_words::table
+    let mut words = load_deck_words(&mut conn, deck_id, true)?;
+    if let Some(format) = display.pinyin {
+        words.iter_mut().for_each(|word| word.format_pinyin(format));
+    }
+
+    Ok(Json(DeckWithWords { id, name, description, cover_word_id, words }))
+}
+
+/// Loads every word of a deck in deck order, along with its import context
+/// and, for the deck owner, its deck notes
+pub(crate) fn load_deck_words(
+    conn: &mut SqliteConnection,
+    deck_id: i32,
+    include_notes: bool,
+) -> Result<Vec<DeckWord>, diesel::result::Error> {
+    Ok(deck_words::table
         .filter(deck_words::deck_id.eq(deck_id))
         .inner_join(words::table)
         .select((
@@ -362,52 +363,190 @@ pub async fn view_deck(
             words::traditional,
             words::pinyin,
             words::definition,
+            deck_words::context_line,
+            deck_words::context_source,
+            deck_words::context_timestamp_ms,
+            (
+                deck_words::custom_definition,
+                deck_words::note,
+                deck_words::mnemonic,
+                deck_words::example,
+            ),
         ))
-        .load::<(i32, String, Option<String>, String, String)>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?
+        .order((deck_words::position.asc(), words::word_id.asc()))
+        .load::<(i32, String, Option<String>, String, String, Option<String>, Option<String>, Option<i32>, NoteColumns)>(conn)?
         .into_iter()
-        .map(|(id, simplified, traditional, pinyin, definition)| DeckWord {
+        .map(|(id, simplified, traditional, pinyin, definition, context_line, context_source, context_timestamp_ms, notes)| DeckWord {
             id,
             simplified,
             traditional,
             pinyin,
             definition,
             deck_id,
+            context: WordContext::from_columns(context_line, context_source, context_timestamp_ms),
+            notes: notes_from_columns(notes).filter(|_| include_notes),
+        })
+        .collect())
+}
+
+/// Whether the user studies a public deck they do not own through a subscription
+pub(crate) fn is_subscribed(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    deck_id: i32,
+) -> Result<bool, diesel::result::Error> {
+    let count: i64 = deck_subscriptions::table
+        .inner_join(decks::table)
+        .filter(deck_subscriptions::user_id.eq(user_id))
+        .filter(deck_subscriptions::deck_id.eq(deck_id))
+        .filter(decks::privacy_value.eq(true))
+        .count()
+        .get_result(conn)?;
+    Ok(count > 0)
+}
+
+/// Nullable `deck_words` note columns: custom definition, note, mnemonic, example
+type NoteColumns = (Option<String>, Option<String>, Option<String>, Option<String>);
+
+fn notes_from_columns((definition, note, mnemonic, example): NoteColumns) -> Option<DeckWordNotes> {
+    DeckWordNotes::from_columns(definition, note, mnemonic, example)
+}
+
+/// Exports a deck as an Anki package, optionally with its SRS scheduling
+pub async fn export_deck_anki(
+    Query(params): Query<AnkiExportParams>,
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, user_id }: OwnedDeck,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get()?;
+
+    // Get basic deck info
+    let name: String = decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .select(decks::deck_name)
+        .first(&mut conn)?;
+
+    // Get all words in this deck
+    let deck_entries = deck_words::table
+        .filter(deck_words::deck_id.eq(deck_id))
+        .inner_join(words::table)
+        .select((words::word_id, words::simplified, words::traditional, words::pinyin, words::definition))
+        .load::<(i32, String, Option<String>, String, String)>(&mut conn)?;
+
+    // Current SRS state and review counts of every word, if requested
+    let mut schedules = HashMap::new();
+    if params.scheduling {
+        let word_ids: Vec<i32> = deck_entries.iter().map(|(id, ..)| *id).collect();
+
+        let mut counts: HashMap<i32, (i32, i32)> = HashMap::new();
+        review_log::table
+            .filter(review_log::user_id.eq(user_id))
+            .filter(review_log::word_id.eq_any(&word_ids))
+            .select((review_log::word_id, review_log::rating, review_log::previous_interval))
+            .load::<(i32, i32, Option<i32>)>(&mut conn)?
+            .into_iter()
+            .for_each(|(word_id, rating, previous_interval)| {
+                let (reps, lapses) = counts.entry(word_id).or_default();
+                *reps += 1;
+                if rating == 1 && previous_interval.is_some() {
+                    *lapses += 1;
+                }
+            });
+
+        schedules = srs_reviews::table
+            .filter(srs_reviews::user_id.eq(user_id))
+            .filter(srs_reviews::word_id.eq_any(&word_ids))
+            .select((
+                srs_reviews::word_id,
+                srs_reviews::next_review_date,
+                srs_reviews::interval,
+                srs_reviews::ease_factor,
+                srs_reviews::stability,
+                srs_reviews::difficulty,
+            ))
+            .load::<(i32, NaiveDateTime, i32, f32, Option<f32>, Option<f32>)>(&mut conn)?
+            .into_iter()
+            .map(|(word_id, next_review_date, interval, ease_factor, stability, difficulty)| {
+                let (reps, lapses) = counts.get(&word_id).copied().unwrap_or((1, 0));
+                (word_id, AnkiExportSchedule {
+                    next_review_date,
+                    interval,
+                    ease_factor,
+                    stability,
+                    difficulty,
+                    reps,
+                    lapses,
+                })
+            })
+            .collect();
+    }
+
+    let notes: Vec<AnkiExportNote> = deck_entries
+        .into_iter()
+        .map(|(word_id, simplified, traditional, pinyin, definition)| AnkiExportNote {
+            word_id,
+            simplified,
+            traditional,
+            pinyin,
+            definition,
+            schedule: schedules.remove(&word_id),
         })
         .collect();
 
-    Ok(Json(DeckWithWords { id, name, words }))
+    let package = AnkiExporter::write_package(&name, &notes, Utc::now().naive_utc())
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    let file_name = format!("{}.apkg", name);
+    Ok(utils::attachment(package, "application/octet-stream", &file_name))
+}
+
+/// Exports a deck as CSV with simplified, traditional, pinyin and definition columns
+pub async fn export_deck_csv(
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get()?;
+
+    // Get basic deck info
+    let name: String = decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .select(decks::deck_name)
+        .first(&mut conn)?;
+
+    // Get all words in this deck
+    let deck_entries = deck_words::table
+        .filter(deck_words::deck_id.eq(deck_id))
+        .inner_join(words::table)
+        .select((words::simplified, words::traditional, words::pinyin, words::definition))
+        .load::<(String, Option<String>, String, String)>(&mut conn)?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| AppError::Internal(format!("CSV error: {}", e));
+    writer
+        .write_record(["simplified", "traditional", "pinyin", "definition"])
+        .map_err(csv_error)?;
+    for (simplified, traditional, pinyin, definition) in &deck_entries {
+        writer
+            .write_record([simplified, traditional.as_deref().unwrap_or(""), pinyin, definition])
+            .map_err(csv_error)?;
+    }
+    let body = writer
+        .into_inner()
+        .map_err(|e| AppError::Internal(format!("CSV error: {}", e)))?;
+
+    Ok(utils::attachment(body, "text/csv; charset=utf-8", &format!("{}.csv", name)))
 }
 
 /// Gets all words in a specific deck
 pub async fn get_deck_words(
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
+    CurrentUser(user_id): CurrentUser,
     Json(payload): Json<DeckId>,
-) -> Result<Json<Vec<Word>>, (StatusCode, String)> {
-    // Verify user is logged in
-    let user_id = utils::get_current_user_id(&session)
-        .await
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Not logged in".to_string()))?;
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
+) -> Result<Json<Vec<Word>>, AppError> {
+    let mut conn = pool.get()?;
 
     // Verify deck ownership
-    let deck_owner: i32 = decks::table
-        .filter(decks::deck_id.eq(payload.deck_id))
-        .select(decks::user_id)
-        .first(&mut conn)
-        .map_err(|_| {
-            (StatusCode::FORBIDDEN, "Deck not found or access denied".to_string())
-        })?;
-
-    if deck_owner != user_id {
-        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
-    }
+    utils::verify_deck_owner(&mut conn, payload.deck_id, user_id)?;
 
     // Get all words in the deck
     let words = deck_words::table
@@ -420,38 +559,25 @@ pub async fn get_deck_words(
             words::pinyin,
             words::definition,
         ))
-        .load::<Word>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?;
+        .load::<Word>(&mut conn)?;
 
     Ok(Json(words))
 }
 
 pub async fn start_study_session(
     Path(deck_id): Path<i32>,
+    Query(params): Query<StudySessionQuery>,
+    Query(display): Query<PinyinQuery>,
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
-) -> Result<Json<Vec<StudyWord>>, (StatusCode, String)> {
-    // Get current user ID
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
-
-    // Verify deck ownership
-    let deck_owner: i32 = decks::table
-        .filter(decks::deck_id.eq(deck_id))
-        .select(decks::user_id)
-        .first(&mut conn)
-        .map_err(|_| (StatusCode::NOT_FOUND, "Deck not found".to_string()))?;
-
-    if deck_owner != user_id {
-        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<StudyWord>>, AppError> {
+    let mut conn = pool.get()?;
+
+    // Verify deck ownership or a subscription
+    let deck_owner = utils::deck_owner(&mut conn, deck_id)?;
+    let subscribed = deck_owner != user_id && is_subscribed(&mut conn, user_id, deck_id)?;
+    if deck_owner != user_id && !subscribed {
+        return Err(AppError::Forbidden("Access denied".to_string()));
     }
 
     // Get all words in the deck
@@ -465,18 +591,25 @@ pub async fn start_study_session(
             words::pinyin,
             words::definition,
             words::added_at,
+            deck_words::context_line,
+            deck_words::context_source,
+            deck_words::context_timestamp_ms,
+            (
+                deck_words::custom_definition,
+                deck_words::note,
+                deck_words::mnemonic,
+                deck_words::example,
+            ),
         ))
-        .load::<(i32, String, Option<String>, String, String, NaiveDateTime)>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load words: {}", e))
-        })?;
+        .order((deck_words::position.asc(), words::word_id.asc()))
+        .load::<(i32, String, Option<String>, String, String, NaiveDateTime, Option<String>, Option<String>, Option<i32>, NoteColumns)>(&mut conn)?;
 
     // Create SRS engine instance
     let mut srs_engine = SrsEngine::new(&mut conn);
 
     // Process each word - treat missing SRS data as new words
     let mut study_words = Vec::new();
-    for (word_id, simplified, traditional, pinyin, definition, _added_at) in words_with_added_at {
+    for (word_id, simplified, traditional, pinyin, definition, _added_at, context_line, context_source, context_timestamp_ms, notes) in words_with_added_at {
         let last_review = srs_engine.get_last_review(user_id, deck_id, word_id).ok();
         
         study_words.push(StudyWord {
@@ -487,12 +620,22 @@ pub async fn start_study_session(
                 pinyin,
                 definition,
                 deck_id,
+                context: WordContext::from_columns(context_line, context_source, context_timestamp_ms),
+                notes: notes_from_columns(notes).filter(|_| !subscribed),
             },
             is_new: last_review.is_none(),
-            last_performance: last_review.as_ref().map(|r| r.as_ref().map(|rev| rev.performance)).flatten(),
+            last_performance: last_review.as_ref().and_then(|r| r.as_ref().map(|rev| rev.performance)),
             next_review: last_review.as_ref().and_then(|r| r.as_ref().map(|rev| rev.next_review_date)),
         });
     }
+    if let Some(format) = display.pinyin {
+        study_words.iter_mut().for_each(|study_word| study_word.word.format_pinyin(format));
+    }
+
+    // Deck order is the order the words were loaded in
+    if params.order == StudyOrder::Deck {
+        return Ok(Json(study_words));
+    }
 
     // Sort words: new words first, then by next review date (earliest first)
     study_words.sort_by(|a, b| {
@@ -513,34 +656,21 @@ pub async fn start_study_session(
 pub async fn record_word_review(
     Path((deck_id, word_id)): Path<(i32, i32)>,
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
+    CurrentUser(user_id): CurrentUser,
     Json(payload): Json<ReviewRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
+) -> Result<Json<ApiResponse>, AppError> {
     // Validate performance rating
     if payload.performance < 1 || payload.performance > 5 {
-        return Err((StatusCode::BAD_REQUEST, "Performance must be between 1 and 5".to_string()));
+        return Err(AppError::invalid_field("performance", "Performance must be between 1 and 5"));
     }
 
-    // Verify user is logged in
-    let user_id = utils::get_current_user_id(&session)
-        .await
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Not logged in".to_string()))?;
+    let mut conn = pool.get()?;
 
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
-
-    // Verify deck ownership and that the word is in the deck
-    let deck_owner: i32 = decks::table
-        .filter(decks::deck_id.eq(deck_id))
-        .select(decks::user_id)
-        .first(&mut conn)
-        .map_err(|_| {
-            (StatusCode::FORBIDDEN, "Deck not found or access denied".to_string())
-        })?;
-
-    if deck_owner != user_id {
-        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    // Verify deck ownership or a subscription, and that the word is in the deck
+    let deck_owner = utils::deck_owner(&mut conn, deck_id)?;
+    let subscribed = deck_owner != user_id && is_subscribed(&mut conn, user_id, deck_id)?;
+    if deck_owner != user_id && !subscribed {
+        return Err(AppError::Forbidden("Access denied".to_string()));
     }
 
     // Verify word exists in deck
@@ -548,22 +678,16 @@ pub async fn record_word_review(
         .filter(deck_words::deck_id.eq(deck_id))
         .filter(deck_words::word_id.eq(word_id))
         .count()
-        .get_result(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?;
+        .get_result(&mut conn)?;
 
     if word_in_deck == 0 {
-        return Err((StatusCode::NOT_FOUND, "Word not found in deck".to_string()));
+        return Err(AppError::NotFound("Word not found in deck".to_string()));
     }
 
     // Record the review using SRS engine
     let mut srs_engine = SrsEngine::new(&mut conn);
 
-    srs_engine.record_review(user_id, deck_id, word_id, payload.performance)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?;
+    srs_engine.record_review(user_id, deck_id, word_id, payload.performance)?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -572,24 +696,23 @@ pub async fn record_word_review(
 }
 
 pub async fn get_all_due_words(
+    Query(display): Query<PinyinQuery>,
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
-) -> Result<Json<Vec<StudyWord>>, (StatusCode, String)> {
-    // Get current user ID
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<StudyWord>>, AppError> {
+    let mut conn = pool.get()?;
 
     // Get all due words with their details
-    let study_words = srs_reviews::table
+    let mut study_words = srs_reviews::table
         .filter(srs_reviews::user_id.eq(user_id))
         .filter(srs_reviews::next_review_date.le(Utc::now().naive_utc()))
         .inner_join(words::table.on(srs_reviews::word_id.eq(words::word_id)))
+        .left_join(
+            deck_words::table.on(deck_words::deck_id
+                .eq(srs_reviews::deck_id)
+                .and(deck_words::word_id.eq(srs_reviews::word_id))),
+        )
+        .inner_join(decks::table.on(decks::deck_id.eq(srs_reviews::deck_id)))
         .select((
             words::word_id,
             words::simplified,
@@ -599,13 +722,17 @@ pub async fn get_all_due_words(
             srs_reviews::deck_id,
             srs_reviews::performance,
             srs_reviews::next_review_date,
+            decks::user_id,
+            (
+                deck_words::custom_definition.nullable(),
+                deck_words::note.nullable(),
+                deck_words::mnemonic.nullable(),
+                deck_words::example.nullable(),
+            ),
         ))
-        .load::<(i32, String, Option<String>, String, String, i32, i32, NaiveDateTime)>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?
+        .load::<(i32, String, Option<String>, String, String, i32, i32, NaiveDateTime, i32, NoteColumns)>(&mut conn)?
         .into_iter()
-        .map(|(word_id, simplified, traditional, pinyin, definition, deck_id, performance, next_review_date)| {
+        .map(|(word_id, simplified, traditional, pinyin, definition, deck_id, performance, next_review_date, deck_owner, notes)| {
             StudyWord {
                 word: DeckWord {
                     id: word_id,
@@ -614,6 +741,8 @@ pub async fn get_all_due_words(
                     pinyin,
                     definition,
                     deck_id,
+                    context: None,
+                    notes: notes_from_columns(notes).filter(|_| deck_owner == user_id),
                 },
                 is_new: false, 
                 last_performance: Some(performance),
@@ -621,6 +750,9 @@ pub async fn get_all_due_words(
             }
         })
         .collect::<Vec<_>>();
+    if let Some(format) = display.pinyin {
+        study_words.iter_mut().for_each(|study_word| study_word.word.format_pinyin(format));
+    }
 
     Ok(Json(study_words))
 }
@@ -628,68 +760,217 @@ pub async fn get_all_due_words(
 #[axum::debug_handler]
 pub async fn get_due_words_count(
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
-) -> Result<Json<i32>, (StatusCode, String)> {
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
-    };
-
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-    })?;
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<i32>, AppError> {
+    let mut conn = pool.get()?;
 
     let count = srs_reviews::table
         .filter(srs_reviews::user_id.eq(user_id))
         .filter(srs_reviews::next_review_date.le(Utc::now().naive_utc()))
         .count()
-        .get_result::<i64>(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?;
+        .get_result::<i64>(&mut conn)?;
 
     Ok(Json(count as i32))
 }
 
 pub async fn update_deck_privacy(
-    Path(deck_id): Path<i32>,
     State(pool): State<DbPool>,
-    session: tower_sessions::Session,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
     Json(payload): Json<UpdatePrivacyRequest>,
-) -> Result<Json<ApiResponse>, (StatusCode, String)> {
-    // Verify user is logged in
-    let user_id = match utils::get_current_user_id(&session).await {
-        Some(id) => id,
-        None => return Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
+
+    // Update the privacy setting
+    diesel::update(decks::table.filter(decks::deck_id.eq(deck_id)))
+        .set(decks::privacy_value.eq(payload.privacy_value))
+        .execute(&mut conn)?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Privacy setting updated successfully".to_string(),
+    }))
+}
+
+pub async fn update_deck_scheduler(
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+    Json(payload): Json<UpdateDeckSchedulerRequest>,
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
+
+    // Update the scheduler override
+    diesel::update(decks::table.filter(decks::deck_id.eq(deck_id)))
+        .set(decks::scheduler.eq(payload.scheduler.map(|kind| kind.as_str())))
+        .execute(&mut conn)?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Deck scheduler updated successfully".to_string(),
+    }))
+}
+
+/// Renames a deck and sets its description and cover word
+pub async fn update_deck(
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+    Json(payload): Json<UpdateDeckRequest>,
+) -> Result<Json<ApiResponse>, AppError> {
+    // Validate the new values before touching the database
+    let name = match payload.name.as_deref().map(str::trim) {
+        Some("") => return Err(AppError::invalid_field("name", "Deck name cannot be empty")),
+        Some(name) if name.chars().count() > MAX_DECK_NAME_LENGTH => {
+            return Err(AppError::invalid_field(
+                "name",
+                format!("Deck name can be at most {} characters long", MAX_DECK_NAME_LENGTH),
+            ));
+        }
+        name => name,
     };
+    let description = payload.description.map(|description| {
+        description
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    });
+    if let Some(Some(text)) = &description
+        && text.chars().count() > MAX_DECK_DESCRIPTION_LENGTH
+    {
+        return Err(AppError::invalid_field(
+            "description",
+            format!("Description can be at most {} characters long", MAX_DECK_DESCRIPTION_LENGTH),
+        ));
+    }
+
+    let mut conn = pool.get()?;
 
-    let mut conn = pool.get().map_err(|e| {
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+    // The cover word has to be one of the deck's words
+    if let Some(Some(word_id)) = payload.cover_word_id {
+        let in_deck: i64 = deck_words::table
+            .filter(deck_words::deck_id.eq(deck_id))
+            .filter(deck_words::word_id.eq(word_id))
+            .count()
+            .get_result(&mut conn)?;
+        if in_deck == 0 {
+            return Err(AppError::invalid_field("cover_word_id", "Cover word is not in this deck"));
+        }
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let deck = decks::table.filter(decks::deck_id.eq(deck_id));
+        if let Some(name) = name {
+            diesel::update(deck).set(decks::deck_name.eq(name)).execute(conn)?;
+        }
+        if let Some(description) = description {
+            diesel::update(deck).set(decks::description.eq(description)).execute(conn)?;
+        }
+        if let Some(cover_word_id) = payload.cover_word_id {
+            diesel::update(deck).set(decks::cover_word_id.eq(cover_word_id)).execute(conn)?;
+        }
+        Ok(())
     })?;
 
-    // Verify the deck exists and belongs to this user
-    let deck_owner: i32 = decks::table
-        .filter(decks::deck_id.eq(deck_id))
-        .select(decks::user_id)
-        .first(&mut conn)
-        .map_err(|_| {
-            (StatusCode::FORBIDDEN, "Deck not found or access denied".to_string())
-        })?;
-
-    if deck_owner != user_id {
-        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Deck updated successfully".to_string(),
+    }))
+}
+
+/// Moves the listed words to the front of the deck in the given order,
+/// keeping the relative order of the remaining words
+pub async fn reorder_deck_words(
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+    Json(payload): Json<ReorderDeckRequest>,
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
+
+    // Current order of the deck
+    let current: Vec<i32> = deck_words::table
+        .filter(deck_words::deck_id.eq(deck_id))
+        .select(deck_words::word_id)
+        .order((deck_words::position.asc(), deck_words::word_id.asc()))
+        .load(&mut conn)?;
+
+    let mut listed = HashSet::new();
+    for word_id in &payload.word_ids {
+        if !current.contains(word_id) {
+            return Err(AppError::invalid_field("word_ids", format!("Word {} is not in this deck", word_id)));
+        }
+        if !listed.insert(*word_id) {
+            return Err(AppError::invalid_field("word_ids", format!("Word {} is listed twice", word_id)));
+        }
     }
 
-    // Update the privacy setting
-    diesel::update(decks::table.filter(decks::deck_id.eq(deck_id)))
-        .set(decks::privacy_value.eq(payload.privacy_value))
-        .execute(&mut conn)
-        .map_err(|e| {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
-        })?;
+    let order: Vec<i32> = payload
+        .word_ids
+        .iter()
+        .copied()
+        .chain(current.into_iter().filter(|word_id| !listed.contains(word_id)))
+        .collect();
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        for (position, word_id) in order.iter().enumerate() {
+            diesel::update(
+                deck_words::table
+                    .filter(deck_words::deck_id.eq(deck_id))
+                    .filter(deck_words::word_id.eq(word_id)),
+            )
+            .set(deck_words::position.eq(position as i32))
+            .execute(conn)?;
+        }
+        Ok(())
+    })?;
 
     Ok(Json(ApiResponse {
         success: true,
-        message: "Privacy setting updated successfully".to_string(),
+        message: "Deck order updated successfully".to_string(),
+    }))
+}
+
+/// Sets the deck-specific definition, note, mnemonic and example of a word,
+/// replacing any set before. Omitted or blank fields are cleared.
+pub async fn update_deck_word_notes(
+    Path((_, word_id)): Path<(i32, i32)>,
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+    Json(payload): Json<DeckWordNotes>,
+) -> Result<Json<ApiResponse>, AppError> {
+    // Blank fields clear the stored value
+    let clean = |name: &str, field: Option<String>| -> Result<Option<String>, AppError> {
+        let field = field.map(|text| text.trim().to_string()).filter(|text| !text.is_empty());
+        if field.as_ref().is_some_and(|text| text.chars().count() > MAX_WORD_NOTE_LENGTH) {
+            return Err(AppError::invalid_field(
+                name,
+                format!("Notes can be at most {} characters long", MAX_WORD_NOTE_LENGTH),
+            ));
+        }
+        Ok(field)
+    };
+    let custom_definition = clean("definition", payload.definition)?;
+    let note = clean("note", payload.note)?;
+    let mnemonic = clean("mnemonic", payload.mnemonic)?;
+    let example = clean("example", payload.example)?;
+
+    let mut conn = pool.get()?;
+
+    let updated = diesel::update(
+        deck_words::table
+            .filter(deck_words::deck_id.eq(deck_id))
+            .filter(deck_words::word_id.eq(word_id)),
+    )
+    .set((
+        deck_words::custom_definition.eq(custom_definition),
+        deck_words::note.eq(note),
+        deck_words::mnemonic.eq(mnemonic),
+        deck_words::example.eq(example),
+    ))
+    .execute(&mut conn)?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound("Word not found in deck".to_string()));
+    }
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Word notes updated successfully".to_string(),
     }))
 }
\ No newline at end of file
@@ -0,0 +1,209 @@
+use serde_json::{Value, json};
+
+/// Name of the SQLite collection inside a legacy package
+pub const COLLECTION_FILE: &str = "collection.anki2";
+/// Name of the media manifest inside a package
+pub const MEDIA_FILE: &str = "media";
+/// Separator between note fields in `notes.flds`
+pub const FIELD_SEPARATOR: char = '\x1f';
+/// Stable id of the note type, so repeated exports reuse it in Anki
+pub const MODEL_ID: i64 = 1_716_200_000_000;
+/// Fields of the exported note type, in order
+pub const MODEL_FIELDS: [&str; 4] = ["Simplified", "Traditional", "Pinyin", "Definition"];
+
+/// Anki collection schema version 11, read by every Anki release
+pub const SCHEMA_SQL: &str = "
+CREATE TABLE col (
+    id integer primary key, crt integer not null, mod integer not null,
+    scm integer not null, ver integer not null, dty integer not null,
+    usn integer not null, ls integer not null, conf text not null,
+    models text not null, decks text not null, dconf text not null,
+    tags text not null
+);
+CREATE TABLE notes (
+    id integer primary key, guid text not null, mid integer not null,
+    mod integer not null, usn integer not null, tags text not null,
+    flds text not null, sfld integer not null, csum integer not null,
+    flags integer not null, data text not null
+);
+CREATE TABLE cards (
+    id integer primary key, nid integer not null, did integer not null,
+    ord integer not null, mod integer not null, usn integer not null,
+    type integer not null, queue integer not null, due integer not null,
+    ivl integer not null, factor integer not null, reps integer not null,
+    lapses integer not null, left integer not null, odue integer not null,
+    odid integer not null, flags integer not null, data text not null
+);
+CREATE TABLE revlog (
+    id integer primary key, cid integer not null, usn integer not null,
+    ease integer not null, ivl integer not null, lastIvl integer not null,
+    factor integer not null, time integer not null, type integer not null
+);
+CREATE TABLE graves (
+    usn integer not null, oid integer not null, type integer not null
+);
+CREATE INDEX ix_notes_usn ON notes (usn);
+CREATE INDEX ix_cards_usn ON cards (usn);
+CREATE INDEX ix_revlog_usn ON revlog (usn);
+CREATE INDEX ix_cards_nid ON cards (nid);
+CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+CREATE INDEX ix_revlog_cid ON revlog (cid);
+CREATE INDEX ix_notes_csum ON notes (csum);
+";
+
+/// Checksum Anki uses to detect duplicate notes: the first 8 hex digits of
+/// the SHA-1 of the first field with HTML removed
+pub fn field_checksum(field: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(strip_html(field)).digest().to_string();
+    i64::from_str_radix(&digest[..8], 16).unwrap_or(0)
+}
+
+/// Escapes text for use inside an HTML note field
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Removes tags and decodes the basic entities from an HTML note field
+pub fn strip_html(field: &str) -> String {
+    let mut text = String::with_capacity(field.len());
+    let mut in_tag = false;
+    for c in field.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// Plain text of a note field, without HTML or `[sound:...]` references
+pub fn field_text(field: &str) -> String {
+    let mut text = strip_html(field);
+    while let Some(start) = text.find("[sound:") {
+        match text[start..].find(']') {
+            Some(end) => text.replace_range(start..start + end + 1, ""),
+            None => break,
+        }
+    }
+    text.trim().to_string()
+}
+
+/// Note type with one card showing the hanzi on the front
+pub fn note_type_json(deck_id: i64, modified: i64) -> Value {
+    let fields: Vec<Value> = MODEL_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name, "ord": ord, "sticky": false, "rtl": false,
+                "font": "Arial", "size": 20, "media": []
+            })
+        })
+        .collect();
+
+    json!({
+        "id": MODEL_ID,
+        "name": "ZWCD Chinese",
+        "type": 0,
+        "mod": modified,
+        "usn": -1,
+        "sortf": 0,
+        "did": deck_id,
+        "flds": fields,
+        "tmpls": [{
+            "name": "Recognition",
+            "ord": 0,
+            "qfmt": "<div class=\"hanzi\">{{Simplified}}</div>",
+            "afmt": "{{FrontSide}}<hr id=answer>\
+                     {{#Traditional}}<div class=\"hanzi traditional\">{{Traditional}}</div>{{/Traditional}}\
+                     <div class=\"pinyin\">{{Pinyin}}</div>\
+                     <div class=\"definition\">{{Definition}}</div>",
+            "did": null,
+            "bqfmt": "",
+            "bafmt": ""
+        }],
+        "css": ".card { font-family: Arial; font-size: 20px; text-align: center; }\n\
+                .hanzi { font-size: 48px; }\n\
+                .traditional { font-size: 32px; color: #666; }\n\
+                .pinyin { margin-top: 12px; }\n\
+                .definition { margin-top: 12px; }",
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "latexsvg": false,
+        "req": [[0, "any", [0]]],
+        "tags": [],
+        "vers": []
+    })
+}
+
+/// Deck entry for the `col.decks` map
+pub fn deck_json(deck_id: i64, name: &str, modified: i64) -> Value {
+    json!({
+        "id": deck_id,
+        "name": name,
+        "mod": modified,
+        "usn": -1,
+        "lrnToday": [0, 0],
+        "revToday": [0, 0],
+        "newToday": [0, 0],
+        "timeToday": [0, 0],
+        "collapsed": false,
+        "browserCollapsed": false,
+        "desc": "",
+        "dyn": 0,
+        "conf": 1,
+        "extendNew": 0,
+        "extendRev": 0
+    })
+}
+
+/// Default deck options group, referenced by every exported deck
+pub fn deck_config_json() -> Value {
+    json!({
+        "1": {
+            "id": 1, "mod": 0, "name": "Default", "usn": 0, "maxTaken": 60,
+            "autoplay": true, "timer": 0, "replayq": true, "dyn": false,
+            "new": {
+                "bury": false, "delays": [1.0, 10.0], "initialFactor": 2500,
+                "ints": [1, 4, 0], "order": 1, "perDay": 20
+            },
+            "lapse": {
+                "delays": [10.0], "leechAction": 1, "leechFails": 8,
+                "minInt": 1, "mult": 0.0
+            },
+            "rev": {
+                "bury": false, "ease4": 1.3, "ivlFct": 1.0, "maxIvl": 36500,
+                "perDay": 200, "hardFactor": 1.2
+            }
+        }
+    })
+}
+
+/// Collection-wide settings for the `col.conf` column
+pub fn collection_config_json(next_position: usize) -> Value {
+    json!({
+        "nextPos": next_position,
+        "estTimes": true,
+        "activeDecks": [1],
+        "sortType": "noteFld",
+        "timeLim": 0,
+        "sortBackwards": false,
+        "addToCur": true,
+        "curDeck": 1,
+        "newSpread": 0,
+        "dueCounts": true,
+        "curModel": MODEL_ID,
+        "collapseTime": 1200
+    })
+}
@@ -0,0 +1,151 @@
+use std::io::{Cursor, Write};
+
+use chrono::{NaiveDateTime, NaiveTime};
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Text};
+use serde_json::json;
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use crate::data::models::{AnkiError, AnkiExportNote};
+use crate::features::anki::{
+    COLLECTION_FILE, FIELD_SEPARATOR, MEDIA_FILE, MODEL_ID, SCHEMA_SQL, collection_config_json,
+    deck_config_json, deck_json, escape_html, field_checksum, note_type_json,
+};
+
+/// Builds Anki packages from deck words
+pub struct AnkiExporter;
+
+impl AnkiExporter {
+    /// Writes `notes` into a new Anki collection and zips it with an empty
+    /// media manifest, returning the package bytes
+    pub fn write_package(
+        deck_name: &str,
+        notes: &[AnkiExportNote],
+        now: NaiveDateTime,
+    ) -> Result<Vec<u8>, AnkiError> {
+        let file = tempfile::Builder::new().suffix(".anki2").tempfile()?;
+        {
+            let mut conn = SqliteConnection::establish(&file.path().to_string_lossy())?;
+            conn.batch_execute(SCHEMA_SQL)?;
+            conn.transaction(|conn| Self::write_collection(conn, deck_name, notes, now))?;
+        }
+        let collection = std::fs::read(file.path())?;
+
+        // Zip the collection together with an empty media manifest
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.start_file(COLLECTION_FILE, options)?;
+        zip.write_all(&collection)?;
+        zip.start_file(MEDIA_FILE, options)?;
+        zip.write_all(b"{}")?;
+
+        Ok(zip.finish()?.into_inner())
+    }
+
+    fn write_collection(
+        conn: &mut SqliteConnection,
+        deck_name: &str,
+        notes: &[AnkiExportNote],
+        now: NaiveDateTime,
+    ) -> Result<(), diesel::result::Error> {
+        let now_secs = now.and_utc().timestamp();
+        let now_ms = now.and_utc().timestamp_millis();
+        let deck_id = now_ms;
+
+        // Review due dates are stored as days since the collection was created,
+        // so start the collection on the day of the earliest due card
+        let created = notes
+            .iter()
+            .filter_map(|note| note.schedule.as_ref())
+            .map(|schedule| schedule.next_review_date.date())
+            .chain(std::iter::once(now.date()))
+            .min()
+            .unwrap_or(now.date());
+        let created_secs = created.and_time(NaiveTime::MIN).and_utc().timestamp();
+
+        let mut decks = json!({ "1": deck_json(1, "Default", now_secs) });
+        decks[deck_id.to_string()] = deck_json(deck_id, deck_name, now_secs);
+        let models = json!({ MODEL_ID.to_string(): note_type_json(deck_id, now_secs) });
+
+        diesel::sql_query(
+            "INSERT INTO col (id, crt, mod, scm, ver, dty, usn, ls, conf, models, decks, dconf, tags)
+             VALUES (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')",
+        )
+        .bind::<BigInt, _>(created_secs)
+        .bind::<BigInt, _>(now_ms)
+        .bind::<BigInt, _>(now_ms)
+        .bind::<Text, _>(collection_config_json(notes.len() + 1).to_string())
+        .bind::<Text, _>(models.to_string())
+        .bind::<Text, _>(decks.to_string())
+        .bind::<Text, _>(deck_config_json().to_string())
+        .execute(conn)?;
+
+        for (position, note) in notes.iter().enumerate() {
+            let id = now_ms + position as i64;
+            let fields = [
+                note.simplified.as_str(),
+                note.traditional.as_deref().unwrap_or(""),
+                note.pinyin.as_str(),
+                note.definition.as_str(),
+            ]
+            .map(escape_html);
+            let sort_field = &fields[0];
+
+            diesel::sql_query(
+                "INSERT INTO notes (id, guid, mid, mod, usn, tags, flds, sfld, csum, flags, data)
+                 VALUES (?, ?, ?, ?, -1, '', ?, ?, ?, 0, '')",
+            )
+            .bind::<BigInt, _>(id)
+            .bind::<Text, _>(format!("zwcd-{}", note.word_id))
+            .bind::<BigInt, _>(MODEL_ID)
+            .bind::<BigInt, _>(now_secs)
+            .bind::<Text, _>(fields.join(&FIELD_SEPARATOR.to_string()))
+            .bind::<Text, _>(sort_field)
+            .bind::<BigInt, _>(field_checksum(sort_field))
+            .execute(conn)?;
+
+            // New cards are due by position, review cards by day number
+            let (card_type, due, interval, factor, reps, lapses, data) = match &note.schedule {
+                Some(schedule) => {
+                    let due = (schedule.next_review_date.date() - created).num_days();
+                    let data = match (schedule.stability, schedule.difficulty) {
+                        (Some(s), Some(d)) => json!({ "s": s, "d": d }).to_string(),
+                        _ => String::new(),
+                    };
+                    (
+                        2,
+                        due,
+                        schedule.interval,
+                        (schedule.ease_factor * 1000.0).round() as i32,
+                        schedule.reps,
+                        schedule.lapses,
+                        data,
+                    )
+                }
+                None => (0, position as i64 + 1, 0, 0, 0, 0, String::new()),
+            };
+
+            diesel::sql_query(
+                "INSERT INTO cards (id, nid, did, ord, mod, usn, type, queue, due, ivl, factor,
+                                    reps, lapses, left, odue, odid, flags, data)
+                 VALUES (?, ?, ?, 0, ?, -1, ?, ?, ?, ?, ?, ?, ?, 0, 0, 0, 0, ?)",
+            )
+            .bind::<BigInt, _>(id)
+            .bind::<BigInt, _>(id)
+            .bind::<BigInt, _>(deck_id)
+            .bind::<BigInt, _>(now_secs)
+            .bind::<Integer, _>(card_type)
+            .bind::<Integer, _>(card_type)
+            .bind::<BigInt, _>(due)
+            .bind::<Integer, _>(interval)
+            .bind::<Integer, _>(factor)
+            .bind::<Integer, _>(reps)
+            .bind::<Integer, _>(lapses)
+            .bind::<Text, _>(data)
+            .execute(conn)?;
+        }
+
+        Ok(())
+    }
+}
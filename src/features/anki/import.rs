@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+use chrono::{DateTime, Duration};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Text};
+use serde_json::{Value, json};
+use zip::ZipArchive;
+
+use crate::data::models::{
+    AnkiError, AnkiFieldMapping, AnkiNoteType, AnkiPreview, DictEntry, ImportedSchedule,
+};
+use crate::features::anki::{COLLECTION_FILE, FIELD_SEPARATOR, field_text};
+use crate::features::search::{SearchEngine, SearchIndex};
+
+/// Collection file of packages exported by Anki 2.1 with the legacy option
+const COLLECTION_FILE_21: &str = "collection.anki21";
+/// Zstd-compressed collection of packages exported by Anki 2.1.50+
+const COLLECTION_FILE_21B: &str = "collection.anki21b";
+/// Largest uncompressed collection accepted, so a zip bomb cannot exhaust memory
+const MAX_COLLECTION_BYTES: u64 = 256 * 1024 * 1024;
+/// Sample notes returned per note type in a preview
+const PREVIEW_SAMPLES: usize = 3;
+/// Anki card type of cards in the review queue
+const CARD_TYPE_REVIEW: i32 = 2;
+/// Longest interval carried over from Anki, in days (about a century)
+const MAX_INTERVAL_DAYS: i64 = 36_500;
+
+#[derive(QueryableByName)]
+struct CollectionRow {
+    #[diesel(sql_type = BigInt)]
+    crt: i64,
+    #[diesel(sql_type = Text)]
+    models: String,
+    #[diesel(sql_type = Text)]
+    decks: String,
+}
+
+#[derive(QueryableByName)]
+struct NoteRow {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = BigInt)]
+    mid: i64,
+    #[diesel(sql_type = Text)]
+    flds: String,
+}
+
+#[derive(QueryableByName, Clone, Copy)]
+struct CardRow {
+    #[diesel(sql_type = BigInt)]
+    nid: i64,
+    #[diesel(sql_type = Integer, column_name = "type")]
+    card_type: i32,
+    #[diesel(sql_type = BigInt)]
+    due: i64,
+    #[diesel(sql_type = BigInt)]
+    ivl: i64,
+    #[diesel(sql_type = BigInt)]
+    factor: i64,
+}
+
+struct NoteType {
+    id: i64,
+    name: String,
+    fields: Vec<String>,
+}
+
+struct PackageNote {
+    note_type_id: i64,
+    fields: Vec<String>,   // Field values with HTML removed
+    card: Option<CardRow>, // First card of the note
+}
+
+/// A note resolved into the word data accepted by `deck::add_word_to_deck_internal`
+pub struct MappedNote {
+    pub word_data: Value,
+    pub schedule: Option<ImportedSchedule>,
+}
+
+/// Notes and note types read from an uploaded `.apkg`
+pub struct AnkiPackage {
+    created: i64, // Collection creation time, the origin of review due days
+    note_types: Vec<NoteType>,
+    decks: Vec<String>,
+    notes: Vec<PackageNote>,
+}
+
+impl AnkiPackage {
+    /// Extracts the collection from a package and loads its notes and cards
+    pub fn read(bytes: &[u8]) -> Result<Self, AnkiError> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        if archive.index_for_name(COLLECTION_FILE_21B).is_some() {
+            return Err(AnkiError::UnsupportedCollection);
+        }
+        let name = [COLLECTION_FILE_21, COLLECTION_FILE]
+            .into_iter()
+            .find(|name| archive.index_for_name(name).is_some())
+            .ok_or(AnkiError::MissingCollection)?;
+
+        // SQLite needs the collection on disk
+        let mut collection = Vec::new();
+        archive
+            .by_name(name)?
+            .take(MAX_COLLECTION_BYTES + 1)
+            .read_to_end(&mut collection)?;
+        if collection.len() as u64 > MAX_COLLECTION_BYTES {
+            return Err(AnkiError::CollectionTooLarge(MAX_COLLECTION_BYTES / (1024 * 1024)));
+        }
+        let mut file = tempfile::Builder::new().suffix(".anki2").tempfile()?;
+        file.write_all(&collection)?;
+        let mut conn = SqliteConnection::establish(&file.path().to_string_lossy())?;
+
+        let col: CollectionRow = diesel::sql_query("SELECT crt, models, decks FROM col")
+            .get_result(&mut conn)?;
+        let note_types = parse_note_types(&col.models)?;
+        let decks = parse_deck_names(&col.decks)?;
+
+        // Keep the first card of every note
+        let mut cards: HashMap<i64, CardRow> = HashMap::new();
+        for card in diesel::sql_query("SELECT nid, type, due, ivl, factor FROM cards ORDER BY ord")
+            .load::<CardRow>(&mut conn)?
+        {
+            cards.entry(card.nid).or_insert(card);
+        }
+
+        let notes = diesel::sql_query("SELECT id, mid, flds FROM notes ORDER BY id")
+            .load::<NoteRow>(&mut conn)?
+            .into_iter()
+            .map(|note| PackageNote {
+                note_type_id: note.mid,
+                fields: note.flds.split(FIELD_SEPARATOR).map(field_text).collect(),
+                card: cards.get(&note.id).copied(),
+            })
+            .collect();
+
+        Ok(AnkiPackage {
+            created: col.crt,
+            note_types,
+            decks,
+            notes,
+        })
+    }
+
+    /// Summarizes the note types and decks so the user can pick a field mapping
+    pub fn preview(&self) -> AnkiPreview {
+        let note_types = self
+            .note_types
+            .iter()
+            .map(|note_type| {
+                let notes: Vec<&PackageNote> = self.notes_of(note_type.id).collect();
+                AnkiNoteType {
+                    id: note_type.id,
+                    name: note_type.name.clone(),
+                    fields: note_type.fields.clone(),
+                    note_count: notes.len(),
+                    samples: notes
+                        .iter()
+                        .take(PREVIEW_SAMPLES)
+                        .map(|note| note.fields.clone())
+                        .collect(),
+                }
+            })
+            .filter(|note_type| note_type.note_count > 0)
+            .collect();
+
+        AnkiPreview {
+            decks: self.decks.clone(),
+            note_types,
+            scheduled_cards: self
+                .notes
+                .iter()
+                .filter(|note| note.card.and_then(|card| self.schedule(&card)).is_some())
+                .count(),
+        }
+    }
+
+    /// Note type with the most notes
+    pub fn default_note_type(&self) -> Option<i64> {
+        self.note_types
+            .iter()
+            .max_by_key(|note_type| self.notes_of(note_type.id).count())
+            .map(|note_type| note_type.id)
+    }
+
+    /// Maps every note of a note type onto a word, filling a missing pinyin or
+    /// definition from CC-CEDICT. Returns the mapped notes and how many notes
+    /// could not be resolved into a complete word.
+    pub fn map_notes(
+        &self,
+        note_type_id: i64,
+        mapping: &AnkiFieldMapping,
+        dict: &[DictEntry],
+        index: &SearchIndex,
+        with_scheduling: bool,
+    ) -> Result<(Vec<MappedNote>, usize), AnkiError> {
+        let note_type = self
+            .note_types
+            .iter()
+            .find(|note_type| note_type.id == note_type_id)
+            .ok_or(AnkiError::UnknownNoteType(note_type_id))?;
+
+        // Resolve field names to positions up front
+        let position = |name: &str| {
+            note_type
+                .fields
+                .iter()
+                .position(|field| field == name)
+                .ok_or_else(|| AnkiError::UnknownField(name.to_string()))
+        };
+        let simplified_at = position(&mapping.simplified)?;
+        let traditional_at = mapping.traditional.as_deref().map(position).transpose()?;
+        let pinyin_at = mapping.pinyin.as_deref().map(position).transpose()?;
+        let definition_at = mapping.definition.as_deref().map(position).transpose()?;
+
+        let mut mapped = Vec::new();
+        let mut unresolved = 0;
+
+        for note in self.notes_of(note_type_id) {
+            let field = |at: Option<usize>| {
+                at.and_then(|at| note.fields.get(at))
+                    .filter(|value| !value.is_empty())
+                    .cloned()
+            };
+
+            let Some(simplified) = field(Some(simplified_at)) else {
+                unresolved += 1;
+                continue;
+            };
+            let mut traditional = field(traditional_at);
+            let mut pinyin = field(pinyin_at);
+            let mut definition = field(definition_at).map(Value::String);
+
+            // Fill gaps from the dictionary entry matching the headword
+            if (pinyin.is_none() || definition.is_none() || traditional.is_none())
+                && let Some(entry) = lookup_entry(&simplified, pinyin.as_deref(), dict, index)
+            {
+                traditional.get_or_insert_with(|| entry.traditional.clone());
+                pinyin.get_or_insert_with(|| entry.pinyin.clone());
+                definition.get_or_insert_with(|| json!(entry.definitions));
+            }
+
+            let (Some(pinyin), Some(definitions)) = (pinyin, definition) else {
+                unresolved += 1;
+                continue;
+            };
+
+            mapped.push(MappedNote {
+                word_data: json!({
+                    "simplified": simplified,
+                    "traditional": traditional,
+                    "pinyin": pinyin,
+                    "definitions": definitions,
+                }),
+                schedule: note
+                    .card
+                    .filter(|_| with_scheduling)
+                    .and_then(|card| self.schedule(&card)),
+            });
+        }
+
+        Ok((mapped, unresolved))
+    }
+
+    fn notes_of(&self, note_type_id: i64) -> impl Iterator<Item = &PackageNote> {
+        self.notes
+            .iter()
+            .filter(move |note| note.note_type_id == note_type_id)
+    }
+
+    /// Converts a review card's interval, ease and due day into SRS state.
+    /// New and learning cards have no day-based schedule and import as new,
+    /// like cards whose dates are out of range.
+    fn schedule(&self, card: &CardRow) -> Option<ImportedSchedule> {
+        if card.card_type != CARD_TYPE_REVIEW || card.ivl <= 0 {
+            return None;
+        }
+
+        // Crafted packages can hold any `due` and `ivl`, such cards import as new
+        let due = card.due.checked_mul(86_400)?.checked_add(self.created)?;
+        let next_review_date = DateTime::from_timestamp(due, 0)?.naive_utc();
+        let interval = card.ivl.min(MAX_INTERVAL_DAYS);
+
+        Some(ImportedSchedule {
+            review_date: next_review_date.checked_sub_signed(Duration::days(interval))?,
+            next_review_date,
+            interval: interval as i32,
+            ease_factor: if card.factor > 0 { card.factor as f32 / 1000.0 } else { 2.5 },
+        })
+    }
+}
+
+/// Dictionary entry for a headword, preferring one whose pinyin matches
+fn lookup_entry<'a>(
+    headword: &str,
+    pinyin: Option<&str>,
+    dict: &'a [DictEntry],
+    index: &SearchIndex,
+) -> Option<&'a DictEntry> {
+    let mut entries = index
+        .lookup_headword(headword)
+        .iter()
+        .map(|&id| &dict[id as usize]);
+
+    match pinyin.map(SearchEngine::remove_tones) {
+        Some(wanted) => {
+            let candidates: Vec<&DictEntry> = entries.collect();
+            candidates
+                .iter()
+                .find(|entry| SearchEngine::remove_tones(&entry.pinyin) == wanted)
+                .or(candidates.first())
+                .copied()
+        }
+        None => entries.next(),
+    }
+}
+
+fn parse_note_types(models: &str) -> Result<Vec<NoteType>, AnkiError> {
+    let models: HashMap<String, Value> = serde_json::from_str(models)
+        .map_err(|e| AnkiError::InvalidCollection(format!("note types: {}", e)))?;
+
+    let mut note_types: Vec<NoteType> = models
+        .into_iter()
+        .filter_map(|(key, model)| {
+            let id = model["id"].as_i64().or_else(|| key.parse().ok())?;
+            let mut fields: Vec<(i64, String)> = model["flds"]
+                .as_array()?
+                .iter()
+                .filter_map(|field| Some((field["ord"].as_i64()?, field["name"].as_str()?.to_string())))
+                .collect();
+            fields.sort();
+
+            Some(NoteType {
+                id,
+                name: model["name"].as_str().unwrap_or_default().to_string(),
+                fields: fields.into_iter().map(|(_, name)| name).collect(),
+            })
+        })
+        .collect();
+
+    note_types.sort_by_key(|note_type| note_type.id);
+    Ok(note_types)
+}
+
+fn parse_deck_names(decks: &str) -> Result<Vec<String>, AnkiError> {
+    let decks: HashMap<String, Value> = serde_json::from_str(decks)
+        .map_err(|e| AnkiError::InvalidCollection(format!("decks: {}", e)))?;
+
+    let mut names: Vec<String> = decks
+        .values()
+        .filter_map(|deck| deck["name"].as_str())
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    Ok(names)
+}
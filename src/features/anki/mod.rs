@@ -0,0 +1,7 @@
+pub mod collection;
+pub mod export;
+pub mod import;
+
+pub use collection::*;
+pub use export::*;
+pub use import::*;
\ No newline at end of file
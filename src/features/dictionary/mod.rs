@@ -0,0 +1,5 @@
+pub mod reload;
+pub mod store;
+
+pub use reload::*;
+pub use store::*;
@@ -0,0 +1,28 @@
+use crate::features::dictionary::DictionaryHandle;
+
+/// Reloads the dictionary every time the process receives SIGHUP, so a
+/// newer CC-CEDICT file can be dropped in place without a restart
+#[cfg(unix)]
+pub async fn reload_on_sighup(handle: DictionaryHandle) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            log::error!("Failed to listen for SIGHUP, dictionary reload disabled: {}", e);
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        let handle = handle.clone();
+        match tokio::task::spawn_blocking(move || handle.reload()).await {
+            Ok(Ok(dictionary)) => dictionary.report(),
+            Ok(Err(e)) => log::error!("Dictionary reload failed, keeping the current one: {}", e),
+            Err(e) => log::error!("Dictionary reload failed, keeping the current one: {}", e),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn reload_on_sighup(_handle: DictionaryHandle) {}
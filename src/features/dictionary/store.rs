@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::data::models::{DictEntry, DictionaryError, DictionaryInfo};
+use crate::data::parsing;
+use crate::features::search::SearchIndex;
+
+/// Malformed lines printed individually before the rest are summarised
+const MAX_REPORTED_LINES: usize = 20;
+
+/// A loaded CC-CEDICT file with the search index built over it
+pub struct Dictionary {
+    pub entries: Vec<DictEntry>,
+    pub index: SearchIndex,
+    pub info: DictionaryInfo,
+}
+
+impl Dictionary {
+    /// Parses the file at `path` and builds its search index
+    pub fn load(path: &Path) -> Result<Self, DictionaryError> {
+        let (entries, info) = parsing::parse_cedict(path)?;
+        let index = SearchIndex::build(&entries);
+        Ok(Dictionary { entries, index, info })
+    }
+
+    /// Prints what was loaded, with any malformed lines
+    pub fn report(&self) {
+        let info = &self.info;
+        println!(
+            "Loaded {} dictionary entries from {} (version {}, published {})",
+            info.entries,
+            info.path.display(),
+            info.version.as_deref().unwrap_or("unknown"),
+            info.date.as_deref().unwrap_or("unknown"),
+        );
+
+        for malformed in info.malformed.iter().take(MAX_REPORTED_LINES) {
+            eprintln!("{}:{}: skipped, {}", info.path.display(), malformed.line, malformed.reason);
+        }
+        if info.malformed.len() > MAX_REPORTED_LINES {
+            eprintln!("... and {} more malformed lines", info.malformed.len() - MAX_REPORTED_LINES);
+        }
+        if let Some(declared) = info.declared_entries
+            && declared != info.entries
+        {
+            eprintln!("Dictionary header declares {} entries but {} were loaded", declared, info.entries);
+        }
+    }
+}
+
+/// Shared, swappable handle to the current dictionary. Requests keep the
+/// `Arc<Dictionary>` they started with, so a reload never changes the
+/// entries or index under a request in flight.
+#[derive(Clone)]
+pub struct DictionaryHandle {
+    path: PathBuf,
+    current: Arc<RwLock<Arc<Dictionary>>>,
+}
+
+impl DictionaryHandle {
+    pub fn new(path: PathBuf, dictionary: Dictionary) -> Self {
+        DictionaryHandle {
+            path,
+            current: Arc::new(RwLock::new(Arc::new(dictionary))),
+        }
+    }
+
+    /// The dictionary currently in use
+    pub fn current(&self) -> Arc<Dictionary> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Loads the file again and swaps it in. On error the current
+    /// dictionary stays in use.
+    pub fn reload(&self) -> Result<Arc<Dictionary>, DictionaryError> {
+        let dictionary = Arc::new(Dictionary::load(&self.path)?);
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = dictionary.clone();
+        Ok(dictionary)
+    }
+}
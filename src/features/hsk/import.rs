@@ -0,0 +1,208 @@
+use std::path::Path;
+
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde_json::{Value, json};
+
+use crate::{
+    DbPool,
+    deck,
+    data::models::{DictEntry, HskDeckReport, HskEntry, HskError, HskVersion},
+    features::hsk::{HskList, deck_name},
+    features::search::SearchIndex,
+    features::wordlist::pinyin_matches,
+    schema::{deck_tags, decks, users},
+};
+
+/// Account owning the system decks. It is marked `is_system`, which login
+/// refuses, and registration rejects the address, so the decks stay read-only.
+pub const SYSTEM_USER_EMAIL: &str = "system@zwcd.invalid";
+
+/// Creates the public HSK decks (`user_made = false`) from the HSK word list
+pub struct HskImporter<'a> {
+    dict: &'a [DictEntry],
+    index: &'a SearchIndex,
+}
+
+impl<'a> HskImporter<'a> {
+    pub fn new(dict: &'a [DictEntry], index: &'a SearchIndex) -> Self {
+        HskImporter { dict, index }
+    }
+
+    /// Creates one deck per level. Levels whose deck already exists are left
+    /// untouched, so running the import again only adds missing decks.
+    pub fn import(&self, conn: &mut SqliteConnection, list: &HskList) -> Result<Vec<HskDeckReport>, HskError> {
+        let owner = system_user(conn)?;
+        let mut reports = Vec::new();
+        for (version, level, entries) in list.levels() {
+            let report = conn.transaction(|conn| self.import_level(conn, owner, version, level, &entries))?;
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    fn import_level(
+        &self,
+        conn: &mut SqliteConnection,
+        owner: i32,
+        version: HskVersion,
+        level: u8,
+        entries: &[&HskEntry],
+    ) -> Result<HskDeckReport, DieselError> {
+        let name = deck_name(version, level);
+        let mut report = HskDeckReport {
+            name: name.clone(),
+            deck_id: 0,
+            created: false,
+            words: 0,
+            from_list: 0,
+            skipped: 0,
+        };
+
+        if let Some(deck_id) = decks::table
+            .filter(decks::user_id.eq(owner))
+            .filter(decks::deck_name.eq(&name))
+            .select(decks::deck_id)
+            .first::<i32>(conn)
+            .optional()?
+        {
+            report.deck_id = deck_id;
+            return Ok(report);
+        }
+
+        let deck_id = deck::create_deck_internal(conn, owner, &name, true)?;
+        diesel::update(decks::table.filter(decks::deck_id.eq(deck_id)))
+            .set((
+                decks::user_made.eq(false),
+                decks::description.eq(format!("Vocabulary introduced at {}", name)),
+            ))
+            .execute(conn)?;
+
+        let version_tag = version.label().to_lowercase();
+        let tags = ["hsk".to_string(), format!("{} level {}", version_tag, level), version_tag];
+        for tag in &tags {
+            diesel::insert_or_ignore_into(deck_tags::table)
+                .values((deck_tags::deck_id.eq(deck_id), deck_tags::tag.eq(tag)))
+                .execute(conn)?;
+        }
+
+        for entry in entries {
+            let Some((word_data, in_dictionary)) = self.resolve(entry) else {
+                report.skipped += 1;
+                continue;
+            };
+            match deck::add_word_to_deck_internal(conn, deck_id, word_data) {
+                Ok(_) => {
+                    report.words += 1;
+                    if !in_dictionary {
+                        report.from_list += 1;
+                    }
+                }
+                // Two entries resolved to the same dictionary word
+                Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => report.skipped += 1,
+                Err(e) => return Err(e),
+            }
+        }
+
+        report.deck_id = deck_id;
+        report.created = true;
+        Ok(report)
+    }
+
+    /// Resolves the first reading of an entry against CC-CEDICT, falling back
+    /// to the list's own pinyin and meanings for words CC-CEDICT lacks.
+    /// Returns the word data and whether it came from the dictionary.
+    fn resolve(&self, entry: &HskEntry) -> Option<(Value, bool)> {
+        let form = entry.forms.first()?;
+        let pinyin = form.transcriptions.numeric.as_deref();
+
+        let candidates: Vec<&DictEntry> = self
+            .index
+            .lookup_headword(&entry.simplified)
+            .iter()
+            .map(|&id| &self.dict[id as usize])
+            .filter(|candidate| pinyin.is_none_or(|pinyin| pinyin_matches(pinyin, &candidate.pinyin)))
+            .collect();
+
+        if let Some(first) = candidates.first() {
+            // Merge the definitions of every entry with that reading
+            let definitions: Vec<&str> = candidates
+                .iter()
+                .filter(|candidate| candidate.pinyin == first.pinyin)
+                .flat_map(|candidate| candidate.definitions.iter().map(String::as_str))
+                .collect();
+            return Some((
+                json!({
+                    "simplified": entry.simplified,
+                    "traditional": first.traditional,
+                    "pinyin": first.pinyin,
+                    "definitions": definitions,
+                }),
+                true,
+            ));
+        }
+
+        if form.meanings.is_empty() {
+            return None;
+        }
+        Some((
+            json!({
+                "simplified": entry.simplified,
+                "traditional": form.traditional,
+                "pinyin": pinyin?,
+                "definitions": form.meanings,
+            }),
+            false,
+        ))
+    }
+}
+
+/// Imports the HSK decks from the list at `path` and prints a line per level
+pub fn run(pool: &DbPool, dict: &[DictEntry], index: &SearchIndex, path: &Path) -> Result<(), HskError> {
+    let list = HskList::read(path)?;
+    let mut conn = pool.get()?;
+    let reports = HskImporter::new(dict, index).import(&mut conn, &list)?;
+
+    for report in &reports {
+        if report.created {
+            println!(
+                "{} (deck {}): {} words, {} from the HSK list, {} skipped",
+                report.name, report.deck_id, report.words, report.from_list, report.skipped
+            );
+        } else {
+            println!("{} (deck {}): already exists, left unchanged", report.name, report.deck_id);
+        }
+    }
+    Ok(())
+}
+
+/// Id of the system account, created on first use
+fn system_user(conn: &mut SqliteConnection) -> Result<i32, HskError> {
+    let system_user = users::table
+        .filter(users::is_system.eq(true))
+        .filter(users::email.eq(SYSTEM_USER_EMAIL))
+        .select(users::user_id);
+    if let Some(user_id) = system_user.first(conn).optional()? {
+        return Ok(user_id);
+    }
+
+    // Someone registered the address before it was reserved
+    let taken: i64 = users::table
+        .filter(users::email.eq(SYSTEM_USER_EMAIL))
+        .count()
+        .get_result(conn)?;
+    if taken > 0 {
+        return Err(HskError::SystemEmailTaken(SYSTEM_USER_EMAIL));
+    }
+
+    // The password is a randomly salted hash that is thrown away
+    let secret = bcrypt::hash(SYSTEM_USER_EMAIL, bcrypt::DEFAULT_COST)?;
+    diesel::insert_into(users::table)
+        .values((
+            users::email.eq(SYSTEM_USER_EMAIL),
+            users::password.eq(bcrypt::hash(secret, bcrypt::DEFAULT_COST)?),
+            users::is_system.eq(true),
+        ))
+        .execute(conn)?;
+    Ok(system_user.first(conn)?)
+}
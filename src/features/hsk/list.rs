@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::data::models::{HskEntry, HskError, HskVersion};
+
+/// Location of the complete HSK vocabulary list, next to `cedict_ts.u8`
+pub const DEFAULT_LIST_PATH: &str = "src/data/hsk/complete.json";
+/// HSK 3.0 levels 7 to 9 share a single word list
+const HSK3_ADVANCED_BAND: u8 = 7;
+
+/// The complete HSK vocabulary list grouped by syllabus and level
+pub struct HskList {
+    entries: Vec<HskEntry>,
+    levels: BTreeMap<(HskVersion, u8), Vec<usize>>, // Entry positions, in list order
+}
+
+impl HskList {
+    /// Reads `complete.json` from the complete-hsk-vocabulary project
+    pub fn read(path: &Path) -> Result<Self, HskError> {
+        let entries: Vec<HskEntry> = serde_json::from_slice(&std::fs::read(path)?)?;
+        Ok(Self::new(entries))
+    }
+
+    pub fn new(entries: Vec<HskEntry>) -> Self {
+        let mut levels: BTreeMap<(HskVersion, u8), Vec<usize>> = BTreeMap::new();
+        for (position, entry) in entries.iter().enumerate() {
+            for level in entry.levels.iter().filter_map(|tag| parse_level(tag)) {
+                levels.entry(level).or_default().push(position);
+            }
+        }
+        HskList { entries, levels }
+    }
+
+    /// Every level with the entries introduced at that level
+    pub fn levels(&self) -> impl Iterator<Item = (HskVersion, u8, Vec<&HskEntry>)> {
+        self.levels.iter().map(|(&(version, level), positions)| {
+            let entries = positions.iter().map(|&position| &self.entries[position]).collect();
+            (version, level, entries)
+        })
+    }
+}
+
+/// Name of the system deck for a level, such as "HSK 3.0 Level 7-9"
+pub fn deck_name(version: HskVersion, level: u8) -> String {
+    if version == HskVersion::V3 && level == HSK3_ADVANCED_BAND {
+        format!("{} Level 7-9", version.label())
+    } else {
+        format!("{} Level {}", version.label(), level)
+    }
+}
+
+/// Parses level tags such as "old-3" (HSK 2.0) and "new-7" (HSK 3.0)
+fn parse_level(tag: &str) -> Option<(HskVersion, u8)> {
+    let (syllabus, level) = tag.split_once('-')?;
+    let level: u8 = level.parse().ok()?;
+    match syllabus {
+        "old" if (1..=6).contains(&level) => Some((HskVersion::V2, level)),
+        "new" if level >= 1 => Some((HskVersion::V3, level.min(HSK3_ADVANCED_BAND))),
+        _ => None,
+    }
+}
@@ -0,0 +1,5 @@
+pub mod import;
+pub mod list;
+
+pub use import::*;
+pub use list::*;
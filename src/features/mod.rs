@@ -1,3 +1,9 @@
+pub mod anki;
 pub mod auth;
+pub mod dictionary;
+pub mod hsk;
+pub mod pinyin;
 pub mod search;
-pub mod srs;
\ No newline at end of file
+pub mod srs;
+pub mod subtitles;
+pub mod wordlist;
\ No newline at end of file
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use crate::data::models::{DeckWord, DictEntry, EntryRef, PinyinFormat, Syllable};
+use crate::features::pinyin::{all_syllables, mark_position, parse_pinyin};
+
+/// Zhuyin initials, longest first so `zh` wins over `z`
+const ZHUYIN_INITIALS: &[(&str, &str)] = &[
+    ("zh", "ㄓ"), ("ch", "ㄔ"), ("sh", "ㄕ"),
+    ("b", "ㄅ"), ("p", "ㄆ"), ("m", "ㄇ"), ("f", "ㄈ"), ("d", "ㄉ"), ("t", "ㄊ"), ("n", "ㄋ"), ("l", "ㄌ"),
+    ("g", "ㄍ"), ("k", "ㄎ"), ("h", "ㄏ"), ("j", "ㄐ"), ("q", "ㄑ"), ("x", "ㄒ"), ("r", "ㄖ"),
+    ("z", "ㄗ"), ("c", "ㄘ"), ("s", "ㄙ"),
+];
+
+/// Zhuyin finals, spelled as after an initial (`iou`, `uei`, `uen` in full)
+const ZHUYIN_FINALS: &[(&str, &str)] = &[
+    ("a", "ㄚ"), ("o", "ㄛ"), ("e", "ㄜ"), ("ê", "ㄝ"), ("ai", "ㄞ"), ("ei", "ㄟ"), ("ao", "ㄠ"), ("ou", "ㄡ"),
+    ("an", "ㄢ"), ("en", "ㄣ"), ("ang", "ㄤ"), ("eng", "ㄥ"), ("ong", "ㄨㄥ"), ("er", "ㄦ"),
+    ("i", "ㄧ"), ("ia", "ㄧㄚ"), ("io", "ㄧㄛ"), ("ie", "ㄧㄝ"), ("iao", "ㄧㄠ"), ("iou", "ㄧㄡ"), ("ian", "ㄧㄢ"),
+    ("in", "ㄧㄣ"), ("iang", "ㄧㄤ"), ("ing", "ㄧㄥ"), ("iong", "ㄩㄥ"),
+    ("u", "ㄨ"), ("ua", "ㄨㄚ"), ("uo", "ㄨㄛ"), ("uai", "ㄨㄞ"), ("uei", "ㄨㄟ"), ("uan", "ㄨㄢ"), ("uen", "ㄨㄣ"),
+    ("uang", "ㄨㄤ"), ("ueng", "ㄨㄥ"),
+    ("ü", "ㄩ"), ("üe", "ㄩㄝ"), ("üan", "ㄩㄢ"), ("ün", "ㄩㄣ"),
+];
+
+/// Syllables without a regular initial and final
+const ZHUYIN_SPECIAL: &[(&str, &str)] = &[
+    ("zhi", "ㄓ"), ("chi", "ㄔ"), ("shi", "ㄕ"), ("ri", "ㄖ"), ("zi", "ㄗ"), ("ci", "ㄘ"), ("si", "ㄙ"),
+    ("r", "ㄦ"), ("m", "ㄇ"), ("n", "ㄋ"), ("ng", "ㄫ"), ("hm", "ㄏㄇ"), ("hng", "ㄏㄫ"),
+];
+
+lazy_static! {
+    static ref ZHUYIN_TO_PINYIN: HashMap<String, &'static str> = all_syllables()
+        .filter_map(|letters| zhuyin_letters(letters).map(|zhuyin| (zhuyin, letters)))
+        .collect();
+}
+
+impl Syllable {
+    /// Writes the syllable with a tone mark, such as `nǚ`
+    pub fn to_marks(&self) -> String {
+        let Some(tone @ 1..=4) = self.tone else {
+            return self.letters.clone();
+        };
+
+        let chars: Vec<char> = self.letters.chars().collect();
+        let Some(position) = mark_position(&chars) else {
+            return self.letters.clone();
+        };
+
+        chars
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| if i == position { add_tone_mark(c, tone) } else { c.to_string() })
+            .collect()
+    }
+
+    /// Writes the syllable as in CC-CEDICT, such as `nu:3`
+    pub fn to_numbers(&self) -> String {
+        let letters = self.letters.replace('ü', "u:").replace('Ü', "U:");
+        match self.tone {
+            Some(tone) => format!("{}{}", letters, tone),
+            None => letters,
+        }
+    }
+
+    /// Writes the syllable in bopomofo, such as `ㄋㄩˇ`. `None` for spellings
+    /// zhuyin cannot represent.
+    pub fn to_zhuyin(&self) -> Option<String> {
+        let letters = zhuyin_letters(&self.key())?;
+        Some(match self.tone {
+            Some(2) => format!("{}ˊ", letters),
+            Some(3) => format!("{}ˇ", letters),
+            Some(4) => format!("{}ˋ", letters),
+            Some(5) => format!("˙{}", letters),
+            _ => letters,
+        })
+    }
+
+    /// Reads one bopomofo syllable, such as `ㄋㄩˇ`
+    pub fn from_zhuyin(zhuyin: &str) -> Option<Syllable> {
+        let mut tone = Some(1);
+        let mut letters = String::new();
+        for c in zhuyin.chars() {
+            match c {
+                'ˊ' => tone = Some(2),
+                'ˇ' => tone = Some(3),
+                'ˋ' => tone = Some(4),
+                '˙' => tone = Some(5),
+                'ˉ' => tone = Some(1),
+                other => letters.push(other),
+            }
+        }
+
+        let pinyin = ZHUYIN_TO_PINYIN.get(&letters)?;
+        Some(Syllable {
+            letters: pinyin.to_string(),
+            tone,
+        })
+    }
+}
+
+impl DictEntry {
+    /// Rewrites the reading and the readings of referenced entries in `format`
+    pub fn format_pinyin(&mut self, format: PinyinFormat) {
+        self.pinyin = format_pinyin(&self.pinyin, format);
+        let refs = self
+            .classifiers
+            .iter_mut()
+            .chain(self.variant_of.iter_mut().map(|variant| &mut variant.entry))
+            .chain(self.see_also.iter_mut())
+            .chain(self.abbreviation_of.iter_mut());
+        for entry_ref in refs {
+            entry_ref.format_pinyin(format);
+        }
+    }
+}
+
+impl EntryRef {
+    pub fn format_pinyin(&mut self, format: PinyinFormat) {
+        if let Some(pinyin) = &self.pinyin {
+            self.pinyin = Some(format_pinyin(pinyin, format));
+        }
+    }
+}
+
+impl DeckWord {
+    pub fn format_pinyin(&mut self, format: PinyinFormat) {
+        self.pinyin = format_pinyin(&self.pinyin, format);
+    }
+}
+
+/// Rewrites a pinyin string, such as a `DictEntry` reading, in the requested
+/// format. Words that are not pinyin (Latin letters, punctuation) are kept as-is.
+pub fn format_pinyin(pinyin: &str, format: PinyinFormat) -> String {
+    pinyin
+        .split_whitespace()
+        .map(|word| format_word(word, format).unwrap_or_else(|| word.to_string()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_word(word: &str, format: PinyinFormat) -> Option<String> {
+    let syllables = parse_pinyin(word)?;
+    Some(match format {
+        PinyinFormat::Numbers => syllables.iter().map(Syllable::to_numbers).collect(),
+        PinyinFormat::Marks => join_marked(&syllables),
+        PinyinFormat::Zhuyin => syllables
+            .iter()
+            .map(Syllable::to_zhuyin)
+            .collect::<Option<Vec<_>>>()?
+            .join(" "),
+    })
+}
+
+/// Reads space-separated bopomofo, such as `ㄋㄧˇ ㄏㄠˇ`
+pub fn parse_zhuyin(text: &str) -> Option<Vec<Syllable>> {
+    let syllables = text
+        .split_whitespace()
+        .map(Syllable::from_zhuyin)
+        .collect::<Option<Vec<_>>>()?;
+    (!syllables.is_empty()).then_some(syllables)
+}
+
+/// Whether the text contains bopomofo letters
+pub fn is_zhuyin(text: &str) -> bool {
+    text.chars().any(|c| ('\u{3105}'..='\u{312F}').contains(&c))
+}
+
+/// Joins marked syllables into one word, adding an apostrophe before a
+/// syllable starting with a vowel so `xī'ān` stays distinct from `xiān`
+fn join_marked(syllables: &[Syllable]) -> String {
+    let mut word = String::new();
+    for (i, syllable) in syllables.iter().enumerate() {
+        let marked = syllable.to_marks();
+        let starts_with_vowel = syllable
+            .key()
+            .starts_with(['a', 'e', 'o', 'ê']);
+        if i > 0 && starts_with_vowel {
+            word.push('\'');
+        }
+        word.push_str(&marked);
+    }
+    word
+}
+
+fn add_tone_mark(c: char, tone: u8) -> String {
+    const MARKED: &[(char, [char; 4])] = &[
+        ('a', ['ā', 'á', 'ǎ', 'à']), ('e', ['ē', 'é', 'ě', 'è']), ('i', ['ī', 'í', 'ǐ', 'ì']),
+        ('o', ['ō', 'ó', 'ǒ', 'ò']), ('u', ['ū', 'ú', 'ǔ', 'ù']), ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+        ('A', ['Ā', 'Á', 'Ǎ', 'À']), ('E', ['Ē', 'É', 'Ě', 'È']), ('I', ['Ī', 'Í', 'Ǐ', 'Ì']),
+        ('O', ['Ō', 'Ó', 'Ǒ', 'Ò']), ('U', ['Ū', 'Ú', 'Ǔ', 'Ù']), ('Ü', ['Ǖ', 'Ǘ', 'Ǚ', 'Ǜ']),
+    ];
+    const COMBINING: [char; 4] = ['\u{0304}', '\u{0301}', '\u{030C}', '\u{0300}'];
+
+    let tone = (tone - 1) as usize;
+    match MARKED.iter().find(|(base, _)| *base == c) {
+        Some((_, marked)) => marked[tone].to_string(),
+        None => format!("{}{}", c, COMBINING[tone]),
+    }
+}
+
+/// Toneless bopomofo for toneless lowercase pinyin letters
+fn zhuyin_letters(letters: &str) -> Option<String> {
+    if let Some((_, zhuyin)) = ZHUYIN_SPECIAL.iter().find(|(pinyin, _)| *pinyin == letters) {
+        return Some(zhuyin.to_string());
+    }
+
+    let (initial, rest) = ZHUYIN_INITIALS
+        .iter()
+        .find(|(pinyin, _)| letters.starts_with(pinyin))
+        .map_or(("", letters), |(pinyin, zhuyin)| (*zhuyin, &letters[pinyin.len()..]));
+
+    let final_ = match (initial, letters) {
+        // `y` and `w` only spell medials when there is no initial
+        ("", _) if letters.starts_with("yu") => format!("ü{}", &letters[2..]),
+        ("", _) if letters.starts_with('y') => match &letters[1..] {
+            rest @ ("i" | "in" | "ing") => rest.to_string(),
+            "ou" => "iou".to_string(),
+            rest => format!("i{}", rest),
+        },
+        ("", _) if letters.starts_with('w') => match &letters[1..] {
+            "u" => "u".to_string(),
+            "ei" => "uei".to_string(),
+            "en" => "uen".to_string(),
+            rest => format!("u{}", rest),
+        },
+        // `u` after j, q and x is `ü`
+        ("ㄐ" | "ㄑ" | "ㄒ", _) if rest.starts_with('u') => format!("ü{}", &rest[1..]),
+        _ => match rest {
+            "iu" => "iou".to_string(),
+            "ui" => "uei".to_string(),
+            "un" => "uen".to_string(),
+            rest => rest.to_string(),
+        },
+    };
+
+    let (_, zhuyin_final) = ZHUYIN_FINALS.iter().find(|(pinyin, _)| *pinyin == final_)?;
+    Some(format!("{}{}", initial, zhuyin_final))
+}
+
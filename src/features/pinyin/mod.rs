@@ -0,0 +1,5 @@
+pub mod format;
+pub mod syllable;
+
+pub use format::*;
+pub use syllable::*;
@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+
+use crate::data::models::Syllable;
+
+/// Every toneless Mandarin syllable, plus the interjections and erhua `r`
+/// found in CC-CEDICT
+const SYLLABLES: &[&str] = &[
+    "a", "ai", "an", "ang", "ao",
+    "ba", "bai", "ban", "bang", "bao", "bei", "ben", "beng", "bi", "bian", "biao", "bie", "bin", "bing", "bo", "bu",
+    "ca", "cai", "can", "cang", "cao", "ce", "cen", "ceng", "ci", "cong", "cou", "cu", "cuan", "cui", "cun", "cuo",
+    "cha", "chai", "chan", "chang", "chao", "che", "chen", "cheng", "chi", "chong", "chou", "chu", "chua", "chuai",
+    "chuan", "chuang", "chui", "chun", "chuo",
+    "da", "dai", "dan", "dang", "dao", "de", "dei", "den", "deng", "di", "dia", "dian", "diao", "die", "ding", "diu",
+    "dong", "dou", "du", "duan", "dui", "dun", "duo",
+    "e", "ei", "en", "eng", "er", "ê",
+    "fa", "fan", "fang", "fei", "fen", "feng", "fo", "fou", "fu",
+    "ga", "gai", "gan", "gang", "gao", "ge", "gei", "gen", "geng", "gong", "gou", "gu", "gua", "guai", "guan", "guang",
+    "gui", "gun", "guo",
+    "ha", "hai", "han", "hang", "hao", "he", "hei", "hen", "heng", "hong", "hou", "hu", "hua", "huai", "huan", "huang",
+    "hui", "hun", "huo",
+    "ji", "jia", "jian", "jiang", "jiao", "jie", "jin", "jing", "jiong", "jiu", "ju", "juan", "jue", "jun",
+    "ka", "kai", "kan", "kang", "kao", "ke", "kei", "ken", "keng", "kong", "kou", "ku", "kua", "kuai", "kuan", "kuang",
+    "kui", "kun", "kuo",
+    "la", "lai", "lan", "lang", "lao", "le", "lei", "leng", "li", "lia", "lian", "liang", "liao", "lie", "lin", "ling",
+    "liu", "lo", "long", "lou", "lu", "luan", "lun", "luo", "lü", "lüe",
+    "ma", "mai", "man", "mang", "mao", "me", "mei", "men", "meng", "mi", "mian", "miao", "mie", "min", "ming", "miu",
+    "mo", "mou", "mu",
+    "na", "nai", "nan", "nang", "nao", "ne", "nei", "nen", "neng", "ni", "nian", "niang", "niao", "nie", "nin", "ning",
+    "niu", "nong", "nou", "nu", "nuan", "nun", "nuo", "nü", "nüe",
+    "o", "ou",
+    "pa", "pai", "pan", "pang", "pao", "pei", "pen", "peng", "pi", "pian", "piao", "pie", "pin", "ping", "po", "pou", "pu",
+    "qi", "qia", "qian", "qiang", "qiao", "qie", "qin", "qing", "qiong", "qiu", "qu", "quan", "que", "qun",
+    "ran", "rang", "rao", "re", "ren", "reng", "ri", "rong", "rou", "ru", "rua", "ruan", "rui", "run", "ruo",
+    "sa", "sai", "san", "sang", "sao", "se", "sen", "seng", "si", "song", "sou", "su", "suan", "sui", "sun", "suo",
+    "sha", "shai", "shan", "shang", "shao", "she", "shei", "shen", "sheng", "shi", "shou", "shu", "shua", "shuai",
+    "shuan", "shuang", "shui", "shun", "shuo",
+    "ta", "tai", "tan", "tang", "tao", "te", "tei", "teng", "ti", "tian", "tiao", "tie", "ting", "tong", "tou", "tu",
+    "tuan", "tui", "tun", "tuo",
+    "wa", "wai", "wan", "wang", "wei", "wen", "weng", "wo", "wu",
+    "xi", "xia", "xian", "xiang", "xiao", "xie", "xin", "xing", "xiong", "xiu", "xu", "xuan", "xue", "xun",
+    "ya", "yan", "yang", "yao", "ye", "yi", "yin", "ying", "yo", "yong", "you", "yu", "yuan", "yue", "yun",
+    "za", "zai", "zan", "zang", "zao", "ze", "zei", "zen", "zeng", "zi", "zong", "zou", "zu", "zuan", "zui", "zun", "zuo",
+    "zha", "zhai", "zhan", "zhang", "zhao", "zhe", "zhei", "zhen", "zheng", "zhi", "zhong", "zhou", "zhu", "zhua",
+    "zhuai", "zhuan", "zhuang", "zhui", "zhun", "zhuo",
+    "m", "n", "ng", "hm", "hng", "r",
+];
+
+/// Longest syllable in `SYLLABLES`, in characters
+const MAX_SYLLABLE_CHARS: usize = 6;
+
+lazy_static! {
+    static ref SYLLABLE_SET: HashSet<&'static str> = SYLLABLES.iter().copied().collect();
+}
+
+impl Syllable {
+    /// Whether `letters` (toneless, lowercase, with `ü`) is a Mandarin syllable
+    pub fn is_valid(letters: &str) -> bool {
+        SYLLABLE_SET.contains(letters)
+    }
+
+    /// Toneless lowercase spelling used for lookups
+    pub fn key(&self) -> String {
+        self.letters.to_lowercase()
+    }
+}
+
+/// Every valid toneless syllable
+pub fn all_syllables() -> impl Iterator<Item = &'static str> {
+    SYLLABLES.iter().copied()
+}
+
+/// Splits the base letter from a tone-marked vowel, such as `ǎ` -> (`a`, 3)
+pub fn split_tone_mark(c: char) -> Option<(char, u8)> {
+    let (base, tone) = match c {
+        'ā' => ('a', 1), 'á' => ('a', 2), 'ǎ' => ('a', 3), 'à' => ('a', 4),
+        'ē' => ('e', 1), 'é' => ('e', 2), 'ě' => ('e', 3), 'è' => ('e', 4),
+        'ī' => ('i', 1), 'í' => ('i', 2), 'ǐ' => ('i', 3), 'ì' => ('i', 4),
+        'ō' => ('o', 1), 'ó' => ('o', 2), 'ǒ' => ('o', 3), 'ò' => ('o', 4),
+        'ū' => ('u', 1), 'ú' => ('u', 2), 'ǔ' => ('u', 3), 'ù' => ('u', 4),
+        'ǖ' => ('ü', 1), 'ǘ' => ('ü', 2), 'ǚ' => ('ü', 3), 'ǜ' => ('ü', 4),
+        'Ā' => ('A', 1), 'Á' => ('A', 2), 'Ǎ' => ('A', 3), 'À' => ('A', 4),
+        'Ē' => ('E', 1), 'É' => ('E', 2), 'Ě' => ('E', 3), 'È' => ('E', 4),
+        'Ī' => ('I', 1), 'Í' => ('I', 2), 'Ǐ' => ('I', 3), 'Ì' => ('I', 4),
+        'Ō' => ('O', 1), 'Ó' => ('O', 2), 'Ǒ' => ('O', 3), 'Ò' => ('O', 4),
+        'Ū' => ('U', 1), 'Ú' => ('U', 2), 'Ǔ' => ('U', 3), 'Ù' => ('U', 4),
+        'Ǖ' => ('Ü', 1), 'Ǘ' => ('Ü', 2), 'Ǚ' => ('Ü', 3), 'Ǜ' => ('Ü', 4),
+        'ḿ' => ('m', 2), 'ń' => ('n', 2), 'ň' => ('n', 3), 'ǹ' => ('n', 4),
+        _ => return None,
+    };
+    Some((base, tone))
+}
+
+/// Tone of a combining diacritic, as typed by some input methods after the vowel
+fn combining_tone(c: char) -> Option<u8> {
+    match c {
+        '\u{0304}' => Some(1),
+        '\u{0301}' => Some(2),
+        '\u{030C}' => Some(3),
+        '\u{0300}' => Some(4),
+        _ => None,
+    }
+}
+
+/// Parses pinyin written with tone numbers (`ni3hao3`, `lu:4`, `lv4`), tone
+/// marks (`nǐhǎo`) or no tones at all (`nihao`). Returns `None` unless the
+/// whole text, apart from spaces, apostrophes and hyphens, is made of syllables.
+pub fn parse_pinyin(text: &str) -> Option<Vec<Syllable>> {
+    let mut syllables = Vec::new();
+    // Letters of the current run, each with the tone of its mark if any
+    let mut run: Vec<(char, Option<u8>)> = Vec::new();
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some((base, tone)) = split_tone_mark(c) {
+            run.push((base, Some(tone)));
+        } else if let Some(tone) = combining_tone(c) {
+            let last = run.last_mut()?;
+            last.1 = Some(tone);
+        } else if c == 'u' || c == 'U' {
+            // `u:` is the CC-CEDICT spelling of `ü`
+            if chars.peek() == Some(&':') {
+                chars.next();
+                run.push((if c == 'u' { 'ü' } else { 'Ü' }, None));
+            } else {
+                run.push((c, None));
+            }
+        } else if c == 'v' || c == 'V' {
+            run.push((if c == 'v' { 'ü' } else { 'Ü' }, None));
+        } else if c.is_ascii_alphabetic() || c == 'ü' || c == 'Ü' || c == 'ê' || c == 'Ê' {
+            run.push((c, None));
+        } else if let Some(tone) = c.to_digit(10).filter(|d| (1..=5).contains(d)) {
+            // A tone number closes the last syllable of the run
+            if run.is_empty() {
+                return None;
+            }
+            syllables.extend(syllabify_run(&run)?);
+            syllables.last_mut()?.tone = Some(tone as u8);
+            run.clear();
+        } else if c.is_whitespace() || c == '\'' || c == '’' || c == '-' {
+            syllables.extend(syllabify_run(&run)?);
+            run.clear();
+        } else {
+            return None;
+        }
+    }
+    syllables.extend(syllabify_run(&run)?);
+
+    if syllables.is_empty() {
+        None
+    } else {
+        Some(syllables)
+    }
+}
+
+/// Splits a run of letters into syllables, carrying each tone mark over to
+/// the syllable containing it
+fn syllabify_run(run: &[(char, Option<u8>)]) -> Option<Vec<Syllable>> {
+    if run.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let lengths = syllable_lengths(run)?;
+
+    let mut syllables = Vec::with_capacity(lengths.len());
+    let mut start = 0;
+    for len in lengths {
+        let letters = &run[start..start + len];
+        syllables.push(Syllable {
+            letters: letters.iter().map(|(c, _)| *c).collect(),
+            tone: letters.iter().find_map(|(_, tone)| *tone),
+        });
+        start += len;
+    }
+    Some(syllables)
+}
+
+/// Lengths of the fewest syllables covering the run, by dynamic programming
+/// so that `xianguo` splits as `xian guo` rather than failing on `xiang uo`.
+/// A syllable may hold one tone mark, on the letter that carries it, so
+/// `xīān` and `xīan` split as `xī ān` and `xī an` while `xiān` stays whole.
+fn syllable_lengths(run: &[(char, Option<u8>)]) -> Option<Vec<usize>> {
+    let chars: Vec<char> = run.iter().map(|(c, _)| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let n = chars.len();
+    let mark_fits = |start: usize, end: usize| {
+        let mut marked = (start..end).filter(|&i| run[i].1.is_some());
+        match (marked.next(), marked.next()) {
+            (None, _) => true,
+            (Some(i), None) => mark_position(&chars[start..end]) == Some(i - start),
+            _ => false,
+        }
+    };
+
+    // best[i]: fewest syllables covering chars[i..], with the first length
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; n + 1];
+    best[n] = Some((0, 0));
+    for start in (0..n).rev() {
+        for len in (1..=MAX_SYLLABLE_CHARS.min(n - start)).rev() {
+            let Some((count, _)) = best[start + len] else {
+                continue;
+            };
+            let candidate: String = chars[start..start + len].iter().collect();
+            if Syllable::is_valid(&candidate)
+                && mark_fits(start, start + len)
+                && best[start].is_none_or(|(best_count, _)| count + 1 < best_count)
+            {
+                best[start] = Some((count + 1, len));
+            }
+        }
+    }
+
+    let mut lengths = Vec::new();
+    let mut start = 0;
+    while start < n {
+        let (_, len) = best[start]?;
+        lengths.push(len);
+        start += len;
+    }
+    Some(lengths)
+}
+
+/// Index of the letter carrying the tone mark: `a` or `e` if present, the `o`
+/// of `ou`, otherwise the last vowel. Syllabic `m`/`n` take the mark themselves.
+pub(crate) fn mark_position(chars: &[char]) -> Option<usize> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let is_vowel = |c: &char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'ü' | 'ê');
+
+    if let Some(i) = lower.iter().position(|c| matches!(c, 'a' | 'e' | 'ê')) {
+        return Some(i);
+    }
+    if let Some(i) = lower.windows(2).position(|pair| pair == ['o', 'u']) {
+        return Some(i);
+    }
+    if let Some(i) = lower.iter().rposition(is_vowel) {
+        return Some(i);
+    }
+    lower.iter().position(|c| matches!(c, 'm' | 'n'))
+}
+
+/// Lowercase ASCII pinyin without tones, `ü` folded to `u`, such as
+/// `Lu:3 xing2` -> `luxing`. Characters other than letters are dropped.
+pub fn toneless(pinyin: &str) -> String {
+    pinyin
+        .chars()
+        .map(|c| split_tone_mark(c).map_or(c, |(base, _)| base))
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'ü' | 'v' => 'u',
+            'ê' => 'e',
+            other => other,
+        })
+        .filter(char::is_ascii_alphabetic)
+        .collect()
+}
@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use crate::data::models::DictEntry;
+use crate::features::search::{SearchEngine, SearchIndex};
+
+/// Queries sampled per category
+const SAMPLE_QUERIES: usize = 50;
+/// Number of results returned by `/api/search`
+const RESULT_LIMIT: usize = 15;
+
+/// Compares the indexed search against the linear scan on queries sampled
+/// from the loaded dictionary and prints p50/p99 latencies for both
+pub fn run(dict: &[DictEntry], index: &SearchIndex) {
+    let queries = sample_queries(dict);
+    println!("Benchmarking {} queries over {} entries", queries.len(), dict.len());
+
+    let mut linear = Vec::with_capacity(queries.len());
+    let mut indexed = Vec::with_capacity(queries.len());
+    let mut identical = 0;
+    let mut identical_all = 0;
+
+    for (query, lang) in &queries {
+        let start = Instant::now();
+        let expected = SearchEngine::search_entries_linear(query, dict, Some(lang));
+        linear.push(start.elapsed());
+
+        let start = Instant::now();
+        let actual = SearchEngine::search_entries(query, dict, index, Some(lang));
+        indexed.push(start.elapsed());
+
+        let top = |results: &[(DictEntry, f32)], limit: usize| -> Vec<(String, String)> {
+            results
+                .iter()
+                .take(limit)
+                .map(|(e, _)| (e.traditional.clone(), e.pinyin.clone()))
+                .collect()
+        };
+        if top(&expected, RESULT_LIMIT) == top(&actual, RESULT_LIMIT) {
+            identical += 1;
+        }
+        if top(&expected, usize::MAX) == top(&actual, usize::MAX) {
+            identical_all += 1;
+        }
+    }
+
+    report("linear", &mut linear);
+    report("indexed", &mut indexed);
+    println!(
+        "identical top {} results: {}/{}",
+        RESULT_LIMIT,
+        identical,
+        queries.len()
+    );
+    println!("identical results: {}/{}", identical_all, queries.len());
+}
+
+fn report(label: &str, timings: &mut [Duration]) {
+    if timings.is_empty() {
+        return;
+    }
+
+    timings.sort();
+    let percentile = |p: usize| timings[(timings.len() * p / 100).min(timings.len() - 1)];
+    println!(
+        "{:<8} p50 {:>10.3?}  p99 {:>10.3?}",
+        label,
+        percentile(50),
+        percentile(99)
+    );
+}
+
+/// Picks evenly spaced entries and derives hanzi, pinyin, misspelled pinyin
+/// and english queries from them
+fn sample_queries(dict: &[DictEntry]) -> Vec<(String, &'static str)> {
+    let step = (dict.len() / SAMPLE_QUERIES).max(1);
+    let mut queries = Vec::new();
+
+    for entry in dict.iter().step_by(step).take(SAMPLE_QUERIES) {
+        queries.push((entry.simplified.clone(), "chinese"));
+
+        let toneless = SearchEngine::remove_tones(&entry.pinyin);
+        queries.push((toneless.clone(), "chinese"));
+
+        let mut typo: Vec<char> = toneless.chars().collect();
+        if typo.len() > 3 {
+            typo.swap(1, 2);
+        }
+        queries.push((typo.into_iter().collect(), "chinese"));
+
+        if let Some(word) = entry
+            .definitions
+            .first()
+            .and_then(|d| d.split_whitespace().max_by_key(|w| w.len()))
+        {
+            queries.push((word.to_string(), "english"));
+        }
+    }
+
+    queries
+}
@@ -0,0 +1,80 @@
+use crate::data::models::{QueryInterpretation, QueryLanguage};
+use crate::features::pinyin::{self, is_zhuyin, parse_pinyin};
+use crate::features::search::{SearchIndex, is_hanzi};
+
+/// Definitions an English word must appear in before a query that is also
+/// valid pinyin is read as English, so surnames like `Ma` in definitions do
+/// not turn `ma` into an English query
+const ENGLISH_MIN_ENTRIES: usize = 20;
+
+/// Weight given to the weaker reading of a query that is both pinyin and English
+const SECONDARY_WEIGHT: f32 = 0.9;
+
+/// Decides how a search query should be read
+pub struct LanguageDetector;
+
+impl LanguageDetector {
+    /// Readings for the `lang` parameter, detecting the language when it is
+    /// missing or `auto`
+    pub fn interpret(query: &str, lang: Option<&str>, index: &SearchIndex) -> Vec<QueryInterpretation> {
+        match lang.unwrap_or("auto") {
+            "auto" => LanguageDetector::detect(query, index),
+            "chinese" if query.chars().any(is_hanzi) => vec![interpretation(QueryLanguage::Hanzi, 1.0)],
+            "chinese" => vec![interpretation(QueryLanguage::Pinyin, 1.0)],
+            _ => vec![interpretation(QueryLanguage::English, 1.0)],
+        }
+    }
+
+    /// Hanzi if the query has any, pinyin if it is a sequence of syllables
+    /// (or zhuyin), English otherwise. Toneless pinyin that is also a common
+    /// English word, such as `long` or `you`, is searched both ways, the
+    /// reading with more dictionary entries weighted highest.
+    pub fn detect(query: &str, index: &SearchIndex) -> Vec<QueryInterpretation> {
+        let query = query.trim();
+        if query.chars().any(is_hanzi) {
+            return vec![interpretation(QueryLanguage::Hanzi, 1.0)];
+        }
+        if is_zhuyin(query) {
+            return vec![interpretation(QueryLanguage::Pinyin, 1.0)];
+        }
+
+        let Some(syllables) = parse_pinyin(query) else {
+            return vec![interpretation(QueryLanguage::English, 1.0)];
+        };
+
+        // Tones, `u:` and apostrophes are only written in pinyin
+        let marked = syllables.iter().any(|syllable| syllable.tone.is_some())
+            || query.contains([':', '\'', '’']);
+        if marked {
+            return vec![interpretation(QueryLanguage::Pinyin, 1.0)];
+        }
+
+        let query_lower = query.to_lowercase();
+        let english_entries = query_lower
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .filter(|word| !word.is_empty())
+            .map(|word| index.english_token_count(word))
+            .min()
+            .unwrap_or(0);
+        if english_entries < ENGLISH_MIN_ENTRIES {
+            return vec![interpretation(QueryLanguage::Pinyin, 1.0)];
+        }
+
+        let pinyin_entries = index.pinyin_key_count(&pinyin::toneless(query));
+        if english_entries >= pinyin_entries {
+            vec![
+                interpretation(QueryLanguage::English, 1.0),
+                interpretation(QueryLanguage::Pinyin, SECONDARY_WEIGHT),
+            ]
+        } else {
+            vec![
+                interpretation(QueryLanguage::Pinyin, 1.0),
+                interpretation(QueryLanguage::English, SECONDARY_WEIGHT),
+            ]
+        }
+    }
+}
+
+fn interpretation(language: QueryLanguage, weight: f32) -> QueryInterpretation {
+    QueryInterpretation { language, weight }
+}
@@ -1,7 +1,10 @@
-use crate::data::models::DictEntry;
+use std::collections::HashMap;
+
+use crate::data::models::{DictEntry, QueryInterpretation, QueryLanguage};
+use crate::features::pinyin;
+use crate::features::search::{is_hanzi, LanguageDetector, SearchIndex, ToneQuery};
 use lazy_static::lazy_static;
 use regex::Regex;
-use unidecode::unidecode;
 
 lazy_static! {
     static ref NORMALIZE_RE: Regex = Regex::new(r"[^a-zA-Z\u4e00-\u9fff]").unwrap();
@@ -11,51 +14,151 @@ lazy_static! {
 pub struct SearchEngine;
 
 impl SearchEngine {
+    /// Searches every reading of the query, detecting its language unless
+    /// `lang` names one, and merges the weighted results, best first
+    pub fn search(
+        query: &str,
+        dict: &[DictEntry],
+        index: &SearchIndex,
+        lang: Option<&str>,
+    ) -> (Vec<QueryInterpretation>, Vec<(DictEntry, f32)>) {
+        let interpretations = LanguageDetector::interpret(query, lang, index);
+
+        let mut results: Vec<(DictEntry, f32)> = Vec::new();
+        let mut seen: HashMap<(String, String, String), usize> = HashMap::new();
+        for interpretation in &interpretations {
+            let mode = match interpretation.language {
+                QueryLanguage::Hanzi | QueryLanguage::Pinyin => "chinese",
+                QueryLanguage::English => "english",
+            };
+
+            for (entry, score) in SearchEngine::search_entries(query, dict, index, Some(mode)) {
+                let score = score * interpretation.weight;
+                let key = (entry.traditional.clone(), entry.simplified.clone(), entry.pinyin.clone());
+                match seen.get(&key) {
+                    Some(&i) => results[i].1 = results[i].1.max(score),
+                    None => {
+                        seen.insert(key, results.len());
+                        results.push((entry, score));
+                    }
+                }
+            }
+        }
+
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        (interpretations, results)
+    }
+
+    /// Scores the entries shortlisted by the index and returns matches, best first
     pub fn search_entries(
         query: &str,
         dict: &[DictEntry],
+        index: &SearchIndex,
         lang: Option<&str>,
     ) -> Vec<(DictEntry, f32)> {
         let query_lower = query.to_lowercase();
-        let normalized = NORMALIZE_RE.replace_all(&query_lower, "");
+        let lang = lang.unwrap_or("chinese");
+        let normalized = SearchEngine::normalize_query(&query_lower, lang);
+
+        let candidates = match lang {
+            "chinese" => index.chinese_candidates(&normalized),
+            _ => index.english_candidates(&normalized),
+        };
+
         let mut results = Vec::new();
+        for id in candidates {
+            let entry = &dict[id as usize];
+            let score = SearchEngine::score_entry(entry, &normalized, lang);
+            if score > 0.8 {
+                results.push((entry.clone(), score));
+            }
+        }
 
-        for entry in dict {
-            let score = match lang.unwrap_or("chinese") {
-                "chinese" => SearchEngine::max_similarity(
-                    &normalized,
-                    &[
-                        &entry.simplified,
-                        &entry.traditional,
-                        &PUNCTUATION_RE.replace_all(&entry.pinyin, "").to_lowercase(),
-                        &SearchEngine::remove_tones(&entry.pinyin),
-                    ],
-                ),
-                _ => entry
-                    .definitions
-                    .iter()
-                    .map(|def| {
-                        let clean_def = NORMALIZE_RE.replace_all(def, "").to_lowercase();
-                        SearchEngine::similarity(&normalized, &clean_def)
-                    })
-                    .fold(0.0, f32::max),
-            };
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        SearchEngine::filter_tones(results, &query_lower, lang)
+    }
 
+    /// Reference implementation scoring every entry, kept for benchmarking the index
+    pub fn search_entries_linear(
+        query: &str,
+        dict: &[DictEntry],
+        lang: Option<&str>,
+    ) -> Vec<(DictEntry, f32)> {
+        let query_lower = query.to_lowercase();
+        let lang = lang.unwrap_or("chinese");
+        let normalized = SearchEngine::normalize_query(&query_lower, lang);
+        let mut results = Vec::new();
+
+        for entry in dict {
+            let score = SearchEngine::score_entry(entry, &normalized, lang);
             if score > 0.8 {
                 results.push((entry.clone(), score));
             }
         }
 
         results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        results
+        SearchEngine::filter_tones(results, &query_lower, lang)
+    }
+
+    /// Keeps the results whose reading has the tones written in a pinyin
+    /// query, unless none has them, in which case the toneless results stand
+    fn filter_tones(results: Vec<(DictEntry, f32)>, query_lower: &str, lang: &str) -> Vec<(DictEntry, f32)> {
+        if lang != "chinese" {
+            return results;
+        }
+        let Some(tones) = ToneQuery::parse(query_lower) else {
+            return results;
+        };
+
+        let toned: Vec<_> = results
+            .iter()
+            .filter(|(entry, _)| tones.matches(&entry.pinyin))
+            .cloned()
+            .collect();
+        if toned.is_empty() { results } else { toned }
+    }
+
+    fn score_entry(entry: &DictEntry, normalized: &str, lang: &str) -> f32 {
+        match lang {
+            "chinese" => SearchEngine::max_similarity(
+                normalized,
+                &[
+                    &entry.simplified,
+                    &entry.traditional,
+                    &PUNCTUATION_RE.replace_all(&entry.pinyin, "").to_lowercase(),
+                    &SearchEngine::remove_tones(&entry.pinyin),
+                ],
+            ),
+            _ => entry
+                .definitions
+                .iter()
+                .map(|def| SearchEngine::similarity(normalized, &SearchEngine::clean_definition(def)))
+                .fold(0.0, f32::max),
+        }
+    }
+
+    /// Keeps hanzi and toneless pinyin letters in chinese mode, so `mǎ`, `ma3`
+    /// and `ㄇㄚˇ` all become `ma`, and plain letters otherwise
+    fn normalize_query(query_lower: &str, lang: &str) -> String {
+        match lang {
+            "chinese" if pinyin::is_zhuyin(query_lower) => pinyin::parse_zhuyin(query_lower)
+                .map(|syllables| syllables.iter().map(|syllable| pinyin::toneless(&syllable.letters)).collect())
+                .unwrap_or_default(),
+            "chinese" => query_lower
+                .chars()
+                .map(|c| if is_hanzi(c) { c.to_string() } else { pinyin::toneless(c.encode_utf8(&mut [0; 4])) })
+                .collect(),
+            _ => NORMALIZE_RE.replace_all(query_lower, "").into_owned(),
+        }
+    }
+
+    /// A definition as compared with english queries: lowercase letters and hanzi only
+    pub(crate) fn clean_definition(definition: &str) -> String {
+        NORMALIZE_RE.replace_all(definition, "").to_lowercase()
     }
 
-    fn remove_tones(pinyin: &str) -> String {
-        unidecode(pinyin)
-            .to_lowercase()
-            .chars()
-            .filter(|c| c.is_ascii_alphabetic())
-            .collect()
+    pub(crate) fn remove_tones(pinyin: &str) -> String {
+        pinyin::toneless(pinyin)
     }
 
     fn max_similarity(a: &str, options: &[&str]) -> f32 {
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+
+use crate::data::models::DictEntry;
+use crate::features::search::SearchEngine;
+
+/// Jaro-Winkler score above which `SearchEngine` accepts a fuzzy match
+const JARO_WINKLER_THRESHOLD: f64 = 0.85;
+
+/// Prebuilt lookup tables over the dictionary, used to shortlist
+/// candidate entries before they are scored by `SearchEngine`
+#[derive(Default)]
+pub struct SearchIndex {
+    /// Hanzi unigram postings over simplified and traditional headwords
+    hanzi_grams: HashMap<char, Vec<u32>>,
+    /// Exact simplified and traditional headwords -> entries
+    headwords: HashMap<String, Vec<u32>>,
+    /// Length in characters of the longest headword
+    max_headword_chars: usize,
+    /// Toneless pinyin keys
+    pinyin_keys: FuzzyPostings,
+    /// Headwords containing Latin letters, such as `卡拉OK`, which pinyin
+    /// queries are also scored against
+    latin_headwords: FuzzyPostings,
+    /// Definitions without spaces or punctuation, as scored in english mode
+    english_definitions: FuzzyPostings,
+    /// English definition token -> entries using it
+    english_tokens: HashMap<String, Vec<u32>>,
+}
+
+/// Distinct strings with postings per character and length in characters.
+/// A string close enough to a query to be accepted by `SearchEngine` shares
+/// most of the query's characters, so it is found in the postings of the
+/// query's rarest characters without reading the others.
+#[derive(Default)]
+struct FuzzyPostings {
+    /// Distinct strings, indexed by string id
+    texts: Vec<String>,
+    /// Entries using each string, indexed by string id
+    entries: Vec<Vec<u32>>,
+    /// String -> string id
+    ids: HashMap<String, u32>,
+    /// (character, length in characters) -> strings of that length containing it
+    postings: HashMap<(char, usize), Vec<u32>>,
+    /// (first character, length in characters) -> strings of that length starting with it
+    firsts: HashMap<(char, usize), Vec<u32>>,
+    /// Length in characters of the longest string
+    max_chars: usize,
+}
+
+impl SearchIndex {
+    /// Builds every lookup table in a single pass over the dictionary
+    pub fn build(dict: &[DictEntry]) -> Self {
+        let mut index = SearchIndex::default();
+
+        for (id, entry) in dict.iter().enumerate() {
+            let id = id as u32;
+
+            // Hanzi unigrams from both scripts
+            for c in entry.simplified.chars().chain(entry.traditional.chars()) {
+                if is_hanzi(c) {
+                    push_posting(index.hanzi_grams.entry(c).or_default(), id);
+                }
+            }
+
+            // Exact headwords from both scripts
+            for headword in [&entry.simplified, &entry.traditional] {
+                push_posting(index.headwords.entry(headword.clone()).or_default(), id);
+                index.max_headword_chars = index.max_headword_chars.max(headword.chars().count());
+                if headword.chars().any(|c| c.is_ascii_alphabetic()) {
+                    index.latin_headwords.insert(headword, id);
+                }
+            }
+
+            index.pinyin_keys.insert(&SearchEngine::remove_tones(&entry.pinyin), id);
+
+            // English tokens and the cleaned form of every definition
+            for definition in &entry.definitions {
+                for token in tokenize(&definition.to_lowercase()) {
+                    push_posting(index.english_tokens.entry(token.to_string()).or_default(), id);
+                }
+                index.english_definitions.insert(&SearchEngine::clean_definition(definition), id);
+            }
+        }
+
+        index
+    }
+
+    /// Entries whose simplified or traditional form is exactly `headword`
+    pub fn lookup_headword(&self, headword: &str) -> &[u32] {
+        self.headwords.get(headword).map_or(&[], Vec::as_slice)
+    }
+
+    /// Length in characters of the longest headword in the dictionary
+    pub fn max_headword_chars(&self) -> usize {
+        self.max_headword_chars
+    }
+
+    /// Number of entries whose definitions use the English word `token`
+    pub fn english_token_count(&self, token: &str) -> usize {
+        self.english_tokens.get(token).map_or(0, Vec::len)
+    }
+
+    /// Number of entries whose toneless pinyin is exactly `key`
+    pub fn pinyin_key_count(&self, key: &str) -> usize {
+        self.pinyin_keys.entries_of(key).len()
+    }
+
+    /// Shortlists entries for a normalized chinese-mode query (hanzi and/or pinyin)
+    pub fn chinese_candidates(&self, normalized: &str) -> Vec<u32> {
+        let mut candidates = Vec::new();
+
+        // Any entry sharing a hanzi with the query
+        for c in normalized.chars().filter(|c| is_hanzi(*c)) {
+            if let Some(postings) = self.hanzi_grams.get(&c) {
+                candidates.extend_from_slice(postings);
+            }
+        }
+
+        if normalized.chars().any(|c| c.is_ascii_alphabetic()) {
+            candidates.extend(self.pinyin_keys.candidates(normalized));
+            candidates.extend(self.latin_headwords.candidates(normalized));
+        }
+
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Shortlists entries for a normalized english-mode query: every entry
+    /// with a definition `SearchEngine` could accept
+    pub fn english_candidates(&self, normalized: &str) -> Vec<u32> {
+        let mut candidates = self.english_definitions.candidates(normalized);
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+}
+
+impl FuzzyPostings {
+    fn insert(&mut self, text: &str, entry: u32) {
+        if text.is_empty() {
+            return;
+        }
+
+        let id = match self.ids.get(text) {
+            Some(&id) => id,
+            None => {
+                let id = self.texts.len() as u32;
+                let len = text.chars().count();
+                let mut chars: Vec<char> = text.chars().collect();
+                self.firsts.entry((chars[0], len)).or_default().push(id);
+                chars.sort_unstable();
+                chars.dedup();
+                for c in chars {
+                    self.postings.entry((c, len)).or_default().push(id);
+                }
+
+                self.max_chars = self.max_chars.max(len);
+                self.ids.insert(text.to_string(), id);
+                self.texts.push(text.to_string());
+                self.entries.push(Vec::new());
+                id
+            }
+        };
+        push_posting(&mut self.entries[id as usize], entry);
+    }
+
+    /// Entries using exactly `text`
+    fn entries_of(&self, text: &str) -> &[u32] {
+        self.ids.get(text).map_or(&[], |&id| &self.entries[id as usize])
+    }
+
+    /// Entries using a string that either contains the query or could reach the
+    /// Jaro-Winkler threshold used by `SearchEngine`, judged from the characters
+    /// they share, so no string that would score is ever dropped
+    fn candidates(&self, query: &str) -> Vec<u32> {
+        let query_chars: Vec<char> = query.chars().collect();
+        let query_len = query_chars.len();
+        if query_len == 0 {
+            return Vec::new();
+        }
+
+        let mut ids = Vec::new();
+        let longest = self.max_chars.min((4 * query_len).max(2 * query.len()));
+        for len in 1..=longest {
+            // Strings starting like the query, which the Winkler prefix favours
+            let prefix = query_len.min(len).min(4);
+            if let Some(min_common) = min_common_chars(query_len, query.len(), len, prefix) {
+                let starting = self.firsts.get(&(query_chars[0], len)).map_or(&[][..], Vec::as_slice);
+                let rarest = self.rarest_postings(&query_chars, len, min_common);
+                if starting.len() <= rarest.iter().map(|postings| postings.len()).sum() {
+                    ids.extend_from_slice(starting);
+                } else {
+                    let starts_like_query = |id: &&u32| self.texts[**id as usize].starts_with(query_chars[0]);
+                    ids.extend(rarest.into_iter().flatten().filter(starts_like_query));
+                }
+            }
+
+            // Every other string, which has no common prefix with the query
+            if let Some(min_common) = min_common_chars(query_len, query.len(), len, 0) {
+                for postings in self.rarest_postings(&query_chars, len, min_common) {
+                    ids.extend_from_slice(postings);
+                }
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+
+        let counts = CharCounts::new(&query_chars);
+        let mut entries = Vec::new();
+        for id in ids {
+            let text = &self.texts[id as usize];
+            let matches = text.contains(query) || {
+                let common = counts.common(text);
+                let prefix = text
+                    .chars()
+                    .zip(&query_chars)
+                    .take(4)
+                    .take_while(|(a, b)| a == *b)
+                    .count();
+                jaro_winkler_upper_bound(common, query_len, text.chars().count(), prefix) > JARO_WINKLER_THRESHOLD
+            };
+
+            if matches {
+                entries.extend_from_slice(&self.entries[id as usize]);
+            }
+        }
+
+        entries
+    }
+
+    /// Postings of strings of `len` characters for the fewest, rarest query
+    /// characters such that every string sharing `min_common` characters with
+    /// the query appears in one of them: a string missing at most
+    /// `query_len - min_common` query characters contains one of any
+    /// `query_len - min_common + 1` of them
+    fn rarest_postings(&self, query_chars: &[char], len: usize, min_common: usize) -> Vec<&[u32]> {
+        let mut rarest: Vec<(usize, char)> = query_chars
+            .iter()
+            .map(|&c| (self.postings.get(&(c, len)).map_or(0, Vec::len), c))
+            .collect();
+        rarest.sort_unstable();
+        rarest.truncate(query_chars.len() - min_common + 1);
+        rarest.dedup();
+
+        rarest
+            .into_iter()
+            .filter_map(|(_, c)| self.postings.get(&(c, len)).map(Vec::as_slice))
+            .collect()
+    }
+}
+
+pub(crate) fn is_hanzi(c: char) -> bool {
+    ('\u{4e00}'..='\u{9fff}').contains(&c)
+}
+
+fn push_posting(postings: &mut Vec<u32>, id: u32) {
+    if postings.last() != Some(&id) {
+        postings.push(id);
+    }
+}
+
+/// Fewest characters a string of `len` characters must share with a query of
+/// `query_len` characters (`query_bytes` bytes) to contain it with the ratio
+/// `SearchEngine` accepts or to reach its Jaro-Winkler threshold with a common
+/// prefix of `prefix` characters. `None` when neither is possible at that length.
+fn min_common_chars(query_len: usize, query_bytes: usize, len: usize, prefix: usize) -> Option<usize> {
+    let fuzzy = (1..=query_len.min(len))
+        .find(|&common| jaro_winkler_upper_bound(common, query_len, len, prefix) > JARO_WINKLER_THRESHOLD);
+    // Containment needs the query to be over half of the string's bytes,
+    // which are at least as many as its characters
+    let contained = (len >= query_len && len < 2 * query_bytes).then_some(query_len);
+    fuzzy.into_iter().chain(contained).min()
+}
+
+/// Characters of a query, counted so the characters it shares with many
+/// strings are found without allocating
+struct CharCounts {
+    ascii: [u8; 128],
+    other: Vec<char>,
+}
+
+impl CharCounts {
+    fn new(query: &[char]) -> Self {
+        let mut counts = CharCounts {
+            ascii: [0; 128],
+            other: Vec::new(),
+        };
+        for &c in query {
+            if c.is_ascii() {
+                counts.ascii[c as usize] = counts.ascii[c as usize].saturating_add(1);
+            } else {
+                counts.other.push(c);
+            }
+        }
+        counts
+    }
+
+    /// Characters the query and text have in common, counting repeats
+    fn common(&self, text: &str) -> usize {
+        let mut ascii = self.ascii;
+        let mut other = self.other.clone();
+        text.chars()
+            .filter(|&c| {
+                if c.is_ascii() {
+                    let count = &mut ascii[c as usize];
+                    *count > 0 && {
+                        *count -= 1;
+                        true
+                    }
+                } else {
+                    match other.iter().position(|&o| o == c) {
+                        Some(i) => {
+                            other.swap_remove(i);
+                            true
+                        }
+                        None => false,
+                    }
+                }
+            })
+            .count()
+    }
+}
+
+/// Best Jaro-Winkler score two strings could reach given how many characters
+/// they have in common, assuming no transpositions
+fn jaro_winkler_upper_bound(common: usize, a_len: usize, b_len: usize, prefix: usize) -> f64 {
+    let m = common as f64;
+    let jaro = (m / a_len as f64 + m / b_len.max(1) as f64 + 1.0) / 3.0;
+    if jaro > 0.7 {
+        jaro + 0.1 * prefix as f64 * (1.0 - jaro)
+    } else {
+        jaro
+    }
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|token| !token.is_empty())
+}
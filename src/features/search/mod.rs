@@ -1,3 +1,12 @@
+pub mod benchmark;
+pub mod detect;
 pub mod engine;
+pub mod index;
+pub mod segment;
+pub mod tones;
 
-pub use engine::*;
\ No newline at end of file
+pub use detect::*;
+pub use engine::*;
+pub use index::*;
+pub use segment::*;
+pub use tones::*;
\ No newline at end of file
@@ -0,0 +1,82 @@
+use crate::data::models::{DictEntry, SegmentToken};
+use crate::features::search::{SearchIndex, is_hanzi};
+
+pub struct Segmenter;
+
+impl Segmenter {
+    /// Splits text into dictionary words using dynamic programming over the
+    /// headword table: segmentations leaving the fewest characters outside the
+    /// dictionary win, ties go to the one with the fewest (so longest) words
+    pub fn segment(text: &str, dict: &[DictEntry], index: &SearchIndex) -> Vec<SegmentToken> {
+        let chars: Vec<char> = text.chars().collect();
+        let n = chars.len();
+        let max_len = index.max_headword_chars().max(1);
+
+        // best[i] = (unknown characters, words, start of last piece) for chars[..i]
+        let mut best: Vec<Option<(usize, usize, usize)>> = vec![None; n + 1];
+        best[0] = Some((0, 0, 0));
+
+        for start in 0..n {
+            let Some((unknown, words, _)) = best[start] else {
+                continue;
+            };
+
+            let mut word = String::new();
+            for end in start + 1..=n.min(start + max_len) {
+                word.push(chars[end - 1]);
+                let known = !index.lookup_headword(&word).is_empty();
+
+                // Single characters are always allowed, longer pieces only if known
+                let cost = match (known, end - start) {
+                    (true, _) => (unknown, words + 1),
+                    (false, 1) => (unknown + 1, words + 1),
+                    (false, _) => continue,
+                };
+
+                if best[end].is_none_or(|(u, w, _)| cost < (u, w)) {
+                    best[end] = Some((cost.0, cost.1, start));
+                }
+            }
+        }
+
+        // Walk back through the chosen pieces
+        let mut pieces = Vec::new();
+        let mut end = n;
+        while end > 0 {
+            let (_, _, start) = best[end].expect("every prefix is reachable one character at a time");
+            pieces.push((start, end));
+            end = start;
+        }
+        pieces.reverse();
+
+        let mut tokens: Vec<SegmentToken> = Vec::new();
+        for (start, end) in pieces {
+            let text: String = chars[start..end].iter().collect();
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            let entries: Vec<DictEntry> = index
+                .lookup_headword(&text)
+                .iter()
+                .map(|&id| dict[id as usize].clone())
+                .collect();
+
+            // Merge runs of unknown non-hanzi characters (latin, digits, punctuation)
+            if entries.is_empty()
+                && !text.chars().any(is_hanzi)
+                && let Some(last) = tokens.last_mut()
+                && last.entries.is_empty()
+                && !last.text.chars().any(is_hanzi)
+                && last.start + last.text.chars().count() == start
+            {
+                last.text.push_str(&text);
+                continue;
+            }
+
+            tokens.push(SegmentToken { text, start, entries });
+        }
+
+        tokens
+    }
+}
@@ -0,0 +1,67 @@
+use crate::features::pinyin::{is_zhuyin, parse_pinyin, parse_zhuyin};
+
+/// Tones requested by a pinyin query such as `ma3` or `nǐhao`, checked per
+/// syllable against entry readings
+pub struct ToneQuery {
+    key: String,               // Toneless query, keeping `ü` apart from `u`
+    toned: Vec<TonedSyllable>, // Syllables the query gave a tone for
+}
+
+/// A query syllable with a tone, located by its offset in the toneless key
+struct TonedSyllable {
+    start: usize,
+    key: String,
+    tone: u8,
+}
+
+impl ToneQuery {
+    /// `None` when the query is not pinyin or zhuyin or gives no tones at all,
+    /// in which case every tone matches
+    pub fn parse(query: &str) -> Option<Self> {
+        let syllables = if is_zhuyin(query) { parse_zhuyin(query)? } else { parse_pinyin(query)? };
+
+        let mut key = String::new();
+        let mut toned = Vec::new();
+        for syllable in &syllables {
+            let syllable_key = syllable.key();
+            if let Some(tone) = syllable.tone {
+                toned.push(TonedSyllable {
+                    start: key.len(),
+                    key: syllable_key.clone(),
+                    tone,
+                });
+            }
+            key.push_str(&syllable_key);
+        }
+
+        (!toned.is_empty()).then_some(ToneQuery { key, toned })
+    }
+
+    /// Whether the query appears in the reading with every given tone on a
+    /// syllable of its own, so `xian1` matches `xian1` but not `xi1 an1`, and
+    /// `ma3` matches `ma3` but neither `ma1` nor `mai3`
+    pub fn matches(&self, entry_pinyin: &str) -> bool {
+        let Some(syllables) = parse_pinyin(entry_pinyin) else {
+            return false;
+        };
+
+        // Start offset, toneless key and tone of every syllable of the reading
+        let mut entry_key = String::new();
+        let mut entry_syllables = Vec::with_capacity(syllables.len());
+        for syllable in &syllables {
+            let syllable_key = syllable.key();
+            entry_syllables.push((entry_key.len(), syllable_key.clone(), syllable.tone));
+            entry_key.push_str(&syllable_key);
+        }
+
+        entry_key
+            .match_indices(&self.key)
+            .any(|(offset, _)| {
+                self.toned.iter().all(|wanted| {
+                    entry_syllables.iter().any(|(start, key, tone)| {
+                        *start == offset + wanted.start && *key == wanted.key && *tone == Some(wanted.tone)
+                    })
+                })
+            })
+    }
+}
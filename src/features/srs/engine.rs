@@ -1,13 +1,15 @@
 // spaced_repetition_system.rs
-use chrono::{Duration, Utc};
+use chrono::Utc;
 use diesel::prelude::*;
 
 use crate::{
-    schema::srs_reviews,
-    data::models::SrsReview
+    schema::{decks, review_log, srs_reviews, user_settings},
+    data::models::{ImportedSchedule, NewReviewLog, SchedulerKind, SrsReview, UserSettings},
+    features::srs::{CardState, MemoryState, Scheduled, Scheduler, scheduler_for},
 };
 
-/// The core SRS engine implementing SM-2 algorithm
+/// The core SRS engine, recording reviews and delegating scheduling to the
+/// `Scheduler` selected by deck or user settings
 pub struct SrsEngine<'a> {
     conn: &'a mut SqliteConnection,
 }
@@ -17,7 +19,7 @@ impl<'a> SrsEngine<'a> {
         SrsEngine { conn }
     }
 
-    /// Records a review for a word and updates its SRS schedule
+    /// Records a review for a word, updates its SRS schedule and appends it to the review log
     pub fn record_review(
         &mut self,
         user_id: i32,
@@ -27,44 +29,125 @@ impl<'a> SrsEngine<'a> {
     ) -> Result<(), diesel::result::Error> {
         // Get the last review if it exists
         let last_review = self.get_last_review(user_id, deck_id, word_id)?;
+        let scheduler = self.scheduler_for_deck(user_id, deck_id)?;
+        let now = Utc::now().naive_utc();
 
-        // Calculate new SRS parameters based on performance
-        let (interval, ease_factor) = match last_review {
-            Some(review) => {
-                self.calculate_srs_parameters(performance, review.interval, review.ease_factor)
-            }
-            None => self.initial_srs_parameters(performance),
+        // Calculate the new schedule from the card's current state
+        let previous = last_review.as_ref().map(|review| CardState {
+            interval: review.interval,
+            ease_factor: review.ease_factor,
+            memory: review
+                .stability
+                .zip(review.difficulty)
+                .map(|(stability, difficulty)| MemoryState { stability, difficulty }),
+        });
+        let elapsed = last_review
+            .as_ref()
+            .map_or(chrono::Duration::zero(), |review| now - review.review_date);
+        let Scheduled { state, due: next_review_date } =
+            scheduler.schedule(previous.as_ref(), performance, elapsed, now);
+        let CardState { interval, ease_factor, memory } = state;
+
+        let log_entry = NewReviewLog {
+            user_id,
+            word_id,
+            deck_id,
+            reviewed_at: now,
+            rating: performance,
+            previous_interval: last_review.as_ref().map(|r| r.interval),
+            new_interval: interval,
+            previous_ease: last_review.as_ref().map(|r| r.ease_factor),
+            new_ease: ease_factor,
+            elapsed_days: last_review.as_ref().map(|_| elapsed.num_days() as i32),
         };
 
-        // Calculate next review date
-        let now = Utc::now().naive_utc();
-        let next_review_date = now + Duration::days(interval as i64);
-
-        // Use upsert (update or insert) operation
-        diesel::insert_into(srs_reviews::table)
-            .values((
-                srs_reviews::word_id.eq(word_id),
-                srs_reviews::deck_id.eq(deck_id),
-                srs_reviews::user_id.eq(user_id),
-                srs_reviews::review_date.eq(now),
-                srs_reviews::next_review_date.eq(next_review_date),
-                srs_reviews::ease_factor.eq(ease_factor),
-                srs_reviews::interval.eq(interval),
-                srs_reviews::performance.eq(performance),
-            ))
-            .on_conflict((srs_reviews::user_id, srs_reviews::word_id))
-            .do_update()
-            .set((
-                srs_reviews::review_date.eq(now),
-                srs_reviews::next_review_date.eq(next_review_date),
-                srs_reviews::ease_factor.eq(ease_factor),
-                srs_reviews::interval.eq(interval),
-                srs_reviews::performance.eq(performance),
-                srs_reviews::deck_id.eq(deck_id), 
-            ))
-            .execute(self.conn)?;
-
-        Ok(())
+        // Update the current card state and log the review atomically
+        self.conn.transaction(|conn| {
+            diesel::insert_into(srs_reviews::table)
+                .values((
+                    srs_reviews::word_id.eq(word_id),
+                    srs_reviews::deck_id.eq(deck_id),
+                    srs_reviews::user_id.eq(user_id),
+                    srs_reviews::review_date.eq(now),
+                    srs_reviews::next_review_date.eq(next_review_date),
+                    srs_reviews::ease_factor.eq(ease_factor),
+                    srs_reviews::interval.eq(interval),
+                    srs_reviews::performance.eq(performance),
+                    srs_reviews::stability.eq(memory.map(|m| m.stability)),
+                    srs_reviews::difficulty.eq(memory.map(|m| m.difficulty)),
+                ))
+                .on_conflict((srs_reviews::user_id, srs_reviews::word_id))
+                .do_update()
+                .set((
+                    srs_reviews::review_date.eq(now),
+                    srs_reviews::next_review_date.eq(next_review_date),
+                    srs_reviews::ease_factor.eq(ease_factor),
+                    srs_reviews::interval.eq(interval),
+                    srs_reviews::performance.eq(performance),
+                    srs_reviews::deck_id.eq(deck_id),
+                    srs_reviews::stability.eq(memory.map(|m| m.stability)),
+                    srs_reviews::difficulty.eq(memory.map(|m| m.difficulty)),
+                ))
+                .execute(conn)?;
+
+            diesel::insert_into(review_log::table)
+                .values(&log_entry)
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    /// Seeds the SRS state of a word from another app, logging it as its first
+    /// review. Words the user already has a schedule for are left untouched.
+    /// Returns whether the state was imported.
+    pub fn import_schedule(
+        &mut self,
+        user_id: i32,
+        deck_id: i32,
+        word_id: i32,
+        schedule: &ImportedSchedule,
+    ) -> Result<bool, diesel::result::Error> {
+        // Imported cards count as answered "Good"
+        let performance = 3;
+
+        self.conn.transaction(|conn| {
+            let inserted = diesel::insert_into(srs_reviews::table)
+                .values((
+                    srs_reviews::word_id.eq(word_id),
+                    srs_reviews::deck_id.eq(deck_id),
+                    srs_reviews::user_id.eq(user_id),
+                    srs_reviews::review_date.eq(schedule.review_date),
+                    srs_reviews::next_review_date.eq(schedule.next_review_date),
+                    srs_reviews::ease_factor.eq(schedule.ease_factor),
+                    srs_reviews::interval.eq(schedule.interval),
+                    srs_reviews::performance.eq(performance),
+                ))
+                .on_conflict((srs_reviews::user_id, srs_reviews::word_id))
+                .do_nothing()
+                .execute(conn)?;
+
+            if inserted == 0 {
+                return Ok(false);
+            }
+
+            diesel::insert_into(review_log::table)
+                .values(&NewReviewLog {
+                    user_id,
+                    word_id,
+                    deck_id,
+                    reviewed_at: schedule.review_date,
+                    rating: performance,
+                    previous_interval: None,
+                    new_interval: schedule.interval,
+                    previous_ease: None,
+                    new_ease: schedule.ease_factor,
+                    elapsed_days: None,
+                })
+                .execute(conn)?;
+
+            Ok(true)
+        })
     }
 
     /// Gets the last review for a word by a user in a deck
@@ -83,49 +166,32 @@ impl<'a> SrsEngine<'a> {
             .optional()
     }
 
-    /// Calculates initial SRS parameters based on first review performance
-    fn initial_srs_parameters(&self, performance: i32) -> (i32, f32) {
-        // Initial ease factor
-        let ease_factor = 2.5;
-
-        // Initial interval based on performance
-        let interval = match performance {
-            1 => 1, // Again - repeat next day
-            2 => 1, // Hard - repeat next day
-            3 => 3, // Good - repeat in 3 days
-            4 => 5, // Easy - repeat in 5 days
-            5 => 7, // Very Easy - repeat in 7 days
-            _ => 1, // Default to 1 day for invalid values
-        };
+    /// Picks the scheduler for a deck: its own override if set, otherwise the user's setting
+    pub fn scheduler_for_deck(
+        &mut self,
+        user_id: i32,
+        deck_id: i32,
+    ) -> Result<Box<dyn Scheduler>, diesel::result::Error> {
+        let settings = self.get_user_settings(user_id)?;
+        let deck_override: Option<String> = decks::table
+            .find(deck_id)
+            .select(decks::scheduler)
+            .first(self.conn)
+            .optional()?
+            .flatten();
 
-        (interval, ease_factor)
+        let kind = deck_override
+            .as_deref()
+            .map_or(settings.scheduler_kind(), SchedulerKind::from_db);
+        Ok(scheduler_for(kind, &settings))
     }
 
-    /// Updates SRS parameters based on performance and previous state
-    fn calculate_srs_parameters(
-        &self,
-        performance: i32,
-        previous_interval: i32,
-        previous_ease: f32,
-    ) -> (i32, f32) {
-        // Calculate new ease factor (minimum 1.3)
-        let mut ease_factor = previous_ease + (0.1 - (5 - performance) as f32 * 0.08);
-        ease_factor = ease_factor.max(1.3);
-
-        // Calculate new interval based on performance
-        let interval = match performance {
-            1 => 1, // Again - reset to 1 day
-            2 => {
-                // Hard - reset interval with 20% penalty
-                (previous_interval as f32 * 0.8).max(1.0) as i32
-            }
-            3 | 4 | 5 => {
-                // Good/Easy - multiply interval by ease factor
-                (previous_interval as f32 * ease_factor).round() as i32
-            }
-            _ => previous_interval,
-        };
-
-        (interval, ease_factor)
+    /// Gets a user's SRS settings, or the defaults if they never changed them
+    pub fn get_user_settings(&mut self, user_id: i32) -> Result<UserSettings, diesel::result::Error> {
+        Ok(user_settings::table
+            .find(user_id)
+            .first(self.conn)
+            .optional()?
+            .unwrap_or_else(|| UserSettings::defaults(user_id)))
     }
 }
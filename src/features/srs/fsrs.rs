@@ -0,0 +1,179 @@
+use chrono::{Duration, NaiveDateTime};
+
+use crate::features::srs::{CardState, Scheduled, Scheduler, due_after};
+
+/// Default FSRS-4.5 model weights
+const DEFAULT_WEIGHTS: [f32; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461,
+    2.1072, 0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+/// Forgetting curve decay
+const DECAY: f32 = -0.5;
+/// Chosen so that retrievability is 90% when elapsed time equals stability
+const FACTOR: f32 = 19.0 / 81.0;
+/// Lower bound for stability, in days
+const MIN_STABILITY: f32 = 0.1;
+/// Upper bound for a scheduled interval, in days
+const MAX_INTERVAL: i32 = 36500;
+
+/// FSRS answer grade
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rating {
+    Again = 1,
+    Hard = 2,
+    Good = 3,
+    Easy = 4,
+}
+
+impl Rating {
+    /// Maps the 1-5 performance scale used by the study page onto FSRS grades
+    pub fn from_performance(performance: i32) -> Self {
+        match performance {
+            ..=1 => Rating::Again,
+            2 => Rating::Hard,
+            3 => Rating::Good,
+            _ => Rating::Easy,
+        }
+    }
+
+    fn value(self) -> f32 {
+        self as i32 as f32
+    }
+}
+
+/// Memory state FSRS keeps for every card
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryState {
+    pub stability: f32,  // Days until recall probability drops to 90%
+    pub difficulty: f32, // Between 1 (easiest) and 10 (hardest)
+}
+
+/// Free Spaced Repetition Scheduler (FSRS-4.5)
+pub struct Fsrs {
+    weights: [f32; 17],
+    desired_retention: f32,
+}
+
+impl Fsrs {
+    pub fn new(desired_retention: f32) -> Self {
+        Fsrs {
+            weights: DEFAULT_WEIGHTS,
+            desired_retention,
+        }
+    }
+
+    /// Computes the memory state after a review
+    pub fn next_state(
+        &self,
+        previous: Option<MemoryState>,
+        rating: Rating,
+        elapsed_days: f32,
+    ) -> MemoryState {
+        let Some(state) = previous else {
+            return MemoryState {
+                stability: self.initial_stability(rating),
+                difficulty: self.initial_difficulty(rating),
+            };
+        };
+
+        let retrievability = self.retrievability(elapsed_days.max(0.0), state.stability);
+        let stability = match rating {
+            Rating::Again => self.forget_stability(state, retrievability),
+            _ => self.recall_stability(state, retrievability, rating),
+        };
+
+        MemoryState {
+            stability: stability.max(MIN_STABILITY),
+            difficulty: self.next_difficulty(state.difficulty, rating),
+        }
+    }
+
+    /// Days until recall probability drops to the desired retention
+    pub fn next_interval(&self, stability: f32) -> i32 {
+        let interval = stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        (interval.round() as i32).clamp(1, MAX_INTERVAL)
+    }
+
+    /// Initial difficulty for a card graded `Good` on its first review
+    fn default_difficulty(&self) -> f32 {
+        self.initial_difficulty(Rating::Good)
+    }
+
+    /// Probability of recalling a card `elapsed_days` after its last review
+    fn retrievability(&self, elapsed_days: f32, stability: f32) -> f32 {
+        (1.0 + FACTOR * elapsed_days / stability).powf(DECAY)
+    }
+
+    fn initial_stability(&self, rating: Rating) -> f32 {
+        self.weights[rating as usize - 1].max(MIN_STABILITY)
+    }
+
+    fn initial_difficulty(&self, rating: Rating) -> f32 {
+        let w = &self.weights;
+        (w[4] - (rating.value() - 3.0) * w[5]).clamp(1.0, 10.0)
+    }
+
+    /// Moves difficulty by the grade, then reverts it towards the default
+    fn next_difficulty(&self, difficulty: f32, rating: Rating) -> f32 {
+        let w = &self.weights;
+        let next = difficulty - w[6] * (rating.value() - 3.0);
+        (w[7] * self.default_difficulty() + (1.0 - w[7]) * next).clamp(1.0, 10.0)
+    }
+
+    fn recall_stability(&self, state: MemoryState, retrievability: f32, rating: Rating) -> f32 {
+        let w = &self.weights;
+        let hard_penalty = if rating == Rating::Hard { w[15] } else { 1.0 };
+        let easy_bonus = if rating == Rating::Easy { w[16] } else { 1.0 };
+
+        state.stability
+            * (1.0
+                + w[8].exp()
+                    * (11.0 - state.difficulty)
+                    * state.stability.powf(-w[9])
+                    * ((w[10] * (1.0 - retrievability)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus)
+    }
+
+    fn forget_stability(&self, state: MemoryState, retrievability: f32) -> f32 {
+        let w = &self.weights;
+        let stability = w[11]
+            * state.difficulty.powf(-w[12])
+            * ((state.stability + 1.0).powf(w[13]) - 1.0)
+            * (w[14] * (1.0 - retrievability)).exp();
+
+        // Forgetting never makes a card more stable
+        stability.min(state.stability)
+    }
+}
+
+impl Scheduler for Fsrs {
+    /// Cards last scheduled with SM-2 start from their current interval as stability
+    fn schedule(
+        &self,
+        previous: Option<&CardState>,
+        performance: i32,
+        elapsed: Duration,
+        reviewed_at: NaiveDateTime,
+    ) -> Scheduled {
+        let memory = previous.map(|card| {
+            card.memory.unwrap_or(MemoryState {
+                stability: card.interval.max(1) as f32,
+                difficulty: self.default_difficulty(),
+            })
+        });
+        let elapsed_days = elapsed.num_seconds() as f32 / 86_400.0;
+
+        let memory = self.next_state(memory, Rating::from_performance(performance), elapsed_days);
+        let interval = self.next_interval(memory.stability);
+
+        Scheduled {
+            state: CardState {
+                interval,
+                ease_factor: previous.map_or(2.5, |card| card.ease_factor),
+                memory: Some(memory),
+            },
+            due: due_after(reviewed_at, interval),
+        }
+    }
+}
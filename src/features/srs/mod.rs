@@ -1,3 +1,9 @@
 pub mod engine;
+pub mod fsrs;
+pub mod scheduler;
+pub mod sm2;
 
-pub use engine::*;
\ No newline at end of file
+pub use engine::*;
+pub use fsrs::*;
+pub use scheduler::*;
+pub use sm2::*;
\ No newline at end of file
@@ -0,0 +1,152 @@
+use chrono::{Duration, NaiveDateTime};
+
+use crate::data::models::{SchedulerKind, UserSettings};
+use crate::features::srs::{Fsrs, MemoryState, Sm2};
+
+/// Scheduling state of a reviewed card
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CardState {
+    pub interval: i32,                // Days until the next review
+    pub ease_factor: f32,             // SM-2 ease, carried unchanged by other schedulers
+    pub memory: Option<MemoryState>,  // FSRS memory state, if the card has one
+}
+
+/// Outcome of scheduling a review
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scheduled {
+    pub state: CardState,
+    pub due: NaiveDateTime,
+}
+
+/// A spaced repetition algorithm. Implementations are pure so they can be
+/// swapped and tested without a database.
+pub trait Scheduler {
+    /// Schedules a card reviewed at `reviewed_at` with a 1-5 `performance`.
+    /// `previous` is `None` for a card reviewed for the first time, and
+    /// `elapsed` is the time since its previous review.
+    fn schedule(
+        &self,
+        previous: Option<&CardState>,
+        performance: i32,
+        elapsed: Duration,
+        reviewed_at: NaiveDateTime,
+    ) -> Scheduled;
+}
+
+/// Builds the scheduler for `kind`, configured from the user's settings
+pub fn scheduler_for(kind: SchedulerKind, settings: &UserSettings) -> Box<dyn Scheduler> {
+    match kind {
+        SchedulerKind::Sm2 => Box::new(Sm2),
+        SchedulerKind::Fsrs => Box::new(Fsrs::new(settings.desired_retention)),
+    }
+}
+
+/// Due date `interval` days after `reviewed_at`
+pub(crate) fn due_after(reviewed_at: NaiveDateTime, interval: i32) -> NaiveDateTime {
+    reviewed_at + Duration::days(interval as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::features::srs::Rating;
+
+    fn reviewed_at() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 1, 1).unwrap().and_hms_opt(9, 0, 0).unwrap()
+    }
+
+    fn sm2_card(interval: i32, ease_factor: f32) -> CardState {
+        CardState {
+            interval,
+            ease_factor,
+            memory: None,
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn sm2_first_review_uses_fixed_intervals() {
+        let scheduled = Sm2.schedule(None, 3, Duration::zero(), reviewed_at());
+
+        assert_eq!(scheduled.state, sm2_card(3, 2.5));
+        assert_eq!(scheduled.due, reviewed_at() + Duration::days(3));
+        assert_eq!(Sm2.schedule(None, 5, Duration::zero(), reviewed_at()).state.interval, 7);
+    }
+
+    #[test]
+    fn sm2_multiplies_interval_by_updated_ease() {
+        let scheduled = Sm2.schedule(Some(&sm2_card(10, 2.5)), 4, Duration::days(10), reviewed_at());
+
+        assert_eq!(scheduled.state.interval, 25);
+        assert_close(scheduled.state.ease_factor, 2.52);
+        assert_eq!(scheduled.due, reviewed_at() + Duration::days(25));
+    }
+
+    #[test]
+    fn sm2_lapses_shorten_interval_and_ease() {
+        let hard = Sm2.schedule(Some(&sm2_card(10, 2.5)), 2, Duration::days(10), reviewed_at());
+        assert_eq!(hard.state.interval, 8);
+        assert_close(hard.state.ease_factor, 2.36);
+
+        let again = Sm2.schedule(Some(&sm2_card(10, 1.3)), 1, Duration::days(10), reviewed_at());
+        assert_eq!(again.state.interval, 1);
+        assert_close(again.state.ease_factor, 1.3);
+        assert_eq!(again.due, reviewed_at() + Duration::days(1));
+    }
+
+    #[test]
+    fn fsrs_first_review_uses_initial_weights() {
+        let fsrs = Fsrs::new(0.9);
+
+        let good = fsrs.schedule(None, 3, Duration::zero(), reviewed_at());
+        let memory = good.state.memory.unwrap();
+        assert_close(memory.stability, 3.7145);
+        assert_close(memory.difficulty, 5.1618);
+        assert_eq!(good.state.interval, 4);
+        assert_eq!(good.due, reviewed_at() + Duration::days(4));
+
+        let easy = fsrs.next_state(None, Rating::Easy, 0.0);
+        assert_close(easy.stability, 13.8206);
+        assert_close(easy.difficulty, 3.932);
+    }
+
+    #[test]
+    fn fsrs_recall_and_lapse_update_memory() {
+        let fsrs = Fsrs::new(0.9);
+        let card = CardState {
+            interval: 4,
+            ease_factor: 2.5,
+            memory: Some(MemoryState {
+                stability: 3.7145,
+                difficulty: 5.1618,
+            }),
+        };
+
+        let good = fsrs.schedule(Some(&card), 3, Duration::days(4), reviewed_at());
+        let memory = good.state.memory.unwrap();
+        assert_close(memory.stability, 14.8081);
+        assert_close(memory.difficulty, 5.1618);
+        assert_eq!(good.state.interval, 15);
+        assert_eq!(good.due, reviewed_at() + Duration::days(15));
+
+        let again = fsrs.schedule(Some(&card), 1, Duration::days(4), reviewed_at());
+        let memory = again.state.memory.unwrap();
+        assert_close(memory.stability, 1.4332);
+        assert_close(memory.difficulty, 6.9012);
+        assert_eq!(again.state.interval, 1);
+    }
+
+    #[test]
+    fn fsrs_interval_matches_stability_at_ninety_percent_retention() {
+        let fsrs = Fsrs::new(0.9);
+
+        assert_eq!(fsrs.next_interval(10.0), 10);
+        assert_eq!(fsrs.next_interval(0.2), 1);
+        assert_eq!(fsrs.next_interval(1.0e6), 36500);
+    }
+}
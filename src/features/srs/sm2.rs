@@ -0,0 +1,73 @@
+use chrono::{Duration, NaiveDateTime};
+
+use crate::features::srs::{CardState, Scheduled, Scheduler, due_after};
+
+/// Ease factor given to new cards
+const INITIAL_EASE: f32 = 2.5;
+/// Lowest ease factor a card can reach
+const MIN_EASE: f32 = 1.3;
+
+/// SM-2 variant with fixed starting intervals
+pub struct Sm2;
+
+impl Scheduler for Sm2 {
+    fn schedule(
+        &self,
+        previous: Option<&CardState>,
+        performance: i32,
+        _elapsed: Duration,
+        reviewed_at: NaiveDateTime,
+    ) -> Scheduled {
+        let (interval, ease_factor) = match previous {
+            Some(card) => next_parameters(performance, card.interval, card.ease_factor),
+            None => initial_parameters(performance),
+        };
+
+        Scheduled {
+            state: CardState {
+                interval,
+                ease_factor,
+                memory: None,
+            },
+            due: due_after(reviewed_at, interval),
+        }
+    }
+}
+
+/// Calculates initial SRS parameters based on first review performance
+fn initial_parameters(performance: i32) -> (i32, f32) {
+    // Initial interval based on performance
+    let interval = match performance {
+        1 => 1, // Again - repeat next day
+        2 => 1, // Hard - repeat next day
+        3 => 3, // Good - repeat in 3 days
+        4 => 5, // Easy - repeat in 5 days
+        5 => 7, // Very Easy - repeat in 7 days
+        _ => 1, // Default to 1 day for invalid values
+    };
+
+    (interval, INITIAL_EASE)
+}
+
+/// Updates SRS parameters based on performance and previous state
+fn next_parameters(performance: i32, previous_interval: i32, previous_ease: f32) -> (i32, f32) {
+    // Calculate new ease factor (minimum 1.3)
+    let ease_factor =
+        (previous_ease + (0.1 - (5 - performance) as f32 * 0.08)).max(MIN_EASE);
+
+    // Calculate new interval based on performance
+    let interval = match performance {
+        1 => 1, // Again - reset to 1 day
+        2 => {
+            // Hard - reset interval with 20% penalty
+            (previous_interval as f32 * 0.8).max(1.0) as i32
+        }
+        3..=5 => {
+            // Good/Easy - multiply interval by ease factor
+            (previous_interval as f32 * ease_factor).round() as i32
+        }
+        _ => previous_interval,
+    };
+
+    (interval, ease_factor)
+}
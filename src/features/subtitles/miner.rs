@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use crate::data::models::{DictEntry, MinedWord, SubtitleLine, WordContext};
+use crate::features::search::{SearchIndex, Segmenter, is_hanzi};
+
+pub struct VocabularyMiner;
+
+impl VocabularyMiner {
+    /// Segments every line and returns the distinct dictionary words found,
+    /// most frequent first, each with the first line it appeared in
+    pub fn mine(
+        lines: &[SubtitleLine],
+        source: &str,
+        dict: &[DictEntry],
+        index: &SearchIndex,
+    ) -> Vec<MinedWord> {
+        let mut words: Vec<MinedWord> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+
+        for line in lines {
+            for token in Segmenter::segment(&line.text, dict, index) {
+                if token.entries.is_empty() || !token.text.chars().any(is_hanzi) {
+                    continue;
+                }
+
+                match positions.get(&token.text) {
+                    Some(&i) => words[i].frequency += 1,
+                    None => {
+                        positions.insert(token.text.clone(), words.len());
+                        words.push(MinedWord {
+                            word: token.text,
+                            frequency: 1,
+                            entries: token.entries,
+                            context: WordContext {
+                                line: line.text.clone(),
+                                source: Some(source.to_string()),
+                                timestamp_ms: Some(line.start_ms),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+
+        // Stable sort keeps first appearance order among equally frequent words
+        words.sort_by_key(|word| std::cmp::Reverse(word.frequency));
+        words
+    }
+}
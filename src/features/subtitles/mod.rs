@@ -0,0 +1,5 @@
+pub mod miner;
+pub mod parser;
+
+pub use miner::*;
+pub use parser::*;
\ No newline at end of file
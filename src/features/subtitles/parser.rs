@@ -0,0 +1,210 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::data::models::{SubtitleError, SubtitleLine};
+
+lazy_static! {
+    static ref TAG_RE: Regex = Regex::new(r"<[^>]*>|\{[^}]*\}").unwrap();
+}
+
+/// Column layout of ASS/SSA dialogue lines when the file has no Format line
+const DEFAULT_ASS_FORMAT: &str = "Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubtitleFormat {
+    Srt,
+    Ass,
+    WebVtt,
+}
+
+impl SubtitleFormat {
+    /// Picks the format from the file extension, falling back to sniffing the content
+    pub fn detect(file_name: &str, content: &str) -> Option<Self> {
+        let extension = file_name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+        match extension.as_deref() {
+            Some("srt") => return Some(SubtitleFormat::Srt),
+            Some("ass") | Some("ssa") => return Some(SubtitleFormat::Ass),
+            Some("vtt") => return Some(SubtitleFormat::WebVtt),
+            _ => {}
+        }
+
+        let start = content.trim_start();
+        if start.starts_with("WEBVTT") {
+            Some(SubtitleFormat::WebVtt)
+        } else if start.starts_with("[Script Info]") || content.contains("\nDialogue:") {
+            Some(SubtitleFormat::Ass)
+        } else if content.contains("-->") {
+            Some(SubtitleFormat::Srt)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct SubtitleParser;
+
+impl SubtitleParser {
+    /// Decodes raw upload bytes as UTF-8 or UTF-16 (by byte order mark)
+    pub fn decode(bytes: &[u8]) -> Result<String, SubtitleError> {
+        let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| from([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16(&units).map_err(|_| SubtitleError::InvalidEncoding)
+        };
+
+        match bytes {
+            [0xEF, 0xBB, 0xBF, rest @ ..] => {
+                String::from_utf8(rest.to_vec()).map_err(|_| SubtitleError::InvalidEncoding)
+            }
+            [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+            [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+            _ => String::from_utf8(bytes.to_vec()).map_err(|_| SubtitleError::InvalidEncoding),
+        }
+    }
+
+    /// Parses subtitle content into timed dialogue lines, dropping empty cues
+    pub fn parse(content: &str, format: SubtitleFormat) -> Result<Vec<SubtitleLine>, SubtitleError> {
+        let lines = match format {
+            SubtitleFormat::Srt | SubtitleFormat::WebVtt => Self::parse_cues(content)?,
+            SubtitleFormat::Ass => Self::parse_ass(content)?,
+        };
+
+        if lines.is_empty() {
+            return Err(SubtitleError::Empty);
+        }
+        Ok(lines)
+    }
+
+    /// SRT and WebVTT share the same cue layout: an optional identifier, a
+    /// `start --> end` timing line, then text lines up to a blank line
+    fn parse_cues(content: &str) -> Result<Vec<SubtitleLine>, SubtitleError> {
+        let mut lines = Vec::new();
+        let mut current: Option<SubtitleLine> = None;
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if let Some((start, _end)) = line.split_once("-->") {
+                if let Some(cue) = current.take() {
+                    Self::push_line(&mut lines, cue);
+                }
+
+                current = Some(SubtitleLine {
+                    start_ms: parse_timestamp(start.trim()).ok_or(SubtitleError::InvalidTimestamp(number + 1))?,
+                    text: String::new(),
+                });
+            } else if line.is_empty() {
+                if let Some(cue) = current.take() {
+                    Self::push_line(&mut lines, cue);
+                }
+            } else if let Some(cue) = current.as_mut() {
+                if !cue.text.is_empty() {
+                    cue.text.push(' ');
+                }
+                cue.text.push_str(line);
+            }
+        }
+
+        if let Some(cue) = current.take() {
+            Self::push_line(&mut lines, cue);
+        }
+        Ok(lines)
+    }
+
+    /// Reads `Dialogue:` lines from the [Events] section using its Format line
+    fn parse_ass(content: &str) -> Result<Vec<SubtitleLine>, SubtitleError> {
+        let mut lines = Vec::new();
+        let mut in_events = false;
+        let mut format: Vec<String> = Self::ass_columns(DEFAULT_ASS_FORMAT);
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.starts_with('[') {
+                in_events = line.eq_ignore_ascii_case("[events]");
+                continue;
+            }
+            if !in_events {
+                continue;
+            }
+
+            if let Some(columns) = line.strip_prefix("Format:") {
+                format = Self::ass_columns(columns);
+            } else if let Some(values) = line.strip_prefix("Dialogue:") {
+                // Text is the last column and may itself contain commas
+                let values: Vec<&str> = values.splitn(format.len(), ',').map(str::trim).collect();
+                let column = |name: &str| {
+                    format
+                        .iter()
+                        .position(|c| c == name)
+                        .and_then(|i| values.get(i).copied())
+                };
+
+                let start_ms = column("start")
+                    .and_then(parse_timestamp)
+                    .ok_or(SubtitleError::InvalidTimestamp(number + 1))?;
+
+                Self::push_line(&mut lines, SubtitleLine {
+                    start_ms,
+                    text: column("text").unwrap_or("").to_string(),
+                });
+            }
+        }
+
+        Ok(lines)
+    }
+
+    fn ass_columns(format: &str) -> Vec<String> {
+        format.split(',').map(|c| c.trim().to_lowercase()).collect()
+    }
+
+    /// Strips formatting and keeps the line if any text is left
+    fn push_line(lines: &mut Vec<SubtitleLine>, mut line: SubtitleLine) {
+        let text = line.text.replace("\\N", " ").replace("\\n", " ").replace("\\h", " ");
+        let text = TAG_RE.replace_all(&text, "");
+        let text = text
+            .replace("&nbsp;", " ")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&");
+
+        line.text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !line.text.is_empty() {
+            lines.push(line);
+        }
+    }
+}
+
+/// Parses `hh:mm:ss,mmm`, `hh:mm:ss.mmm`, `mm:ss.mmm` and ASS `h:mm:ss.cc` timestamps.
+/// Negative components, minutes or seconds past 59 and times too large for
+/// an `i32` of milliseconds are rejected.
+fn parse_timestamp(timestamp: &str) -> Option<i32> {
+    let (clock, fraction) = match timestamp.split_once([',', '.']) {
+        Some((clock, fraction)) => (clock, fraction),
+        None => (timestamp, "0"),
+    };
+
+    let parts: Vec<u32> = clock
+        .split(':')
+        .map(|p| p.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => (0, *m, *s),
+        _ => return None,
+    };
+    if minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+
+    // Fractions are milliseconds in SRT/WebVTT and centiseconds in ASS
+    let digits: String = fraction.chars().take(3).collect();
+    let millis = digits.parse::<u32>().ok()? * 10_u32.pow(3 - digits.len() as u32);
+
+    let total = (hours as i64)
+        .checked_mul(3_600_000)?
+        .checked_add(minutes as i64 * 60_000 + seconds as i64 * 1000 + millis as i64)?;
+    i32::try_from(total).ok()
+}
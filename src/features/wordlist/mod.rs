@@ -0,0 +1,5 @@
+pub mod parser;
+pub mod resolver;
+
+pub use parser::*;
+pub use resolver::*;
\ No newline at end of file
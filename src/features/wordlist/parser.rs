@@ -0,0 +1,61 @@
+use csv::{ReaderBuilder, Trim};
+
+use crate::data::models::{WordListError, WordListRow};
+use crate::features::subtitles::SubtitleParser;
+
+/// Reads CSV and TSV word lists into rows of cells
+pub struct WordListParser;
+
+impl WordListParser {
+    /// Decodes an upload as UTF-8 or UTF-16, like subtitle files
+    pub fn decode(bytes: &[u8]) -> Result<String, WordListError> {
+        SubtitleParser::decode(bytes).map_err(|_| WordListError::InvalidEncoding)
+    }
+
+    /// Tab for `.tsv`/`.txt` files, comma for `.csv`, otherwise whichever
+    /// appears more often in the first line
+    pub fn detect_delimiter(file_name: &str, content: &str) -> u8 {
+        let extension = file_name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+        match extension.as_deref() {
+            Some("tsv") | Some("tab") | Some("txt") => return b'\t',
+            Some("csv") => return b',',
+            _ => {}
+        }
+
+        let first_line = content.lines().find(|line| !line.starts_with('#')).unwrap_or("");
+        if first_line.matches('\t').count() >= first_line.matches(',').count() {
+            b'\t'
+        } else {
+            b','
+        }
+    }
+
+    /// Parses every row, skipping blank lines and `#` comment lines such as
+    /// the headers Anki writes into its text exports
+    pub fn parse(content: &str, delimiter: u8) -> Result<Vec<WordListRow>, WordListError> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::All)
+            .comment(Some(b'#'))
+            .from_reader(content.as_bytes());
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            if record.iter().all(str::is_empty) {
+                continue;
+            }
+            rows.push(WordListRow {
+                line: record.position().map_or(rows.len() + 1, |position| position.line() as usize),
+                cells: record.iter().map(str::to_string).collect(),
+            });
+        }
+
+        if rows.is_empty() {
+            return Err(WordListError::Empty);
+        }
+        Ok(rows)
+    }
+}
@@ -0,0 +1,140 @@
+use serde_json::{Value, json};
+
+use crate::data::models::{
+    DictEntry, RowError, RowErrorKind, WordListColumns, WordListError, WordListRow,
+};
+use crate::features::search::{SearchEngine, SearchIndex};
+
+/// Turns word list rows into the `word_data` accepted by
+/// `deck::add_word_to_deck_internal`, completing them from the dictionary
+pub struct WordResolver<'a> {
+    dict: &'a [DictEntry],
+    index: &'a SearchIndex,
+}
+
+impl<'a> WordResolver<'a> {
+    pub fn new(dict: &'a [DictEntry], index: &'a SearchIndex) -> Self {
+        WordResolver { dict, index }
+    }
+
+    /// Checks that every mapped column exists in at least one row
+    pub fn validate_columns(columns: &WordListColumns, rows: &[WordListRow]) -> Result<(), WordListError> {
+        let width = rows.iter().map(|row| row.cells.len()).max().unwrap_or(0);
+        [Some(columns.simplified), columns.traditional, columns.pinyin, columns.definition]
+            .into_iter()
+            .flatten()
+            .find(|&column| column >= width)
+            .map_or(Ok(()), |column| Err(WordListError::InvalidColumn(column)))
+    }
+
+    /// Resolves a row, taking pinyin and definitions from the file when mapped
+    /// and otherwise from the single dictionary reading that fits
+    pub fn resolve(&self, row: &WordListRow, columns: &WordListColumns) -> Result<Value, RowError> {
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|column| row.cells.get(column))
+                .filter(|value| !value.is_empty())
+                .cloned()
+        };
+
+        let simplified = cell(Some(columns.simplified))
+            .ok_or_else(|| row_error(row, RowErrorKind::MissingField, "Missing hanzi".to_string()))?;
+        let traditional = cell(columns.traditional);
+        let pinyin = cell(columns.pinyin);
+        let definition = cell(columns.definition);
+
+        // Full rows are added as written
+        if let (Some(pinyin), Some(definition)) = (&pinyin, &definition) {
+            let traditional = traditional.or_else(|| {
+                self.entries(&simplified)
+                    .find(|entry| pinyin_matches(pinyin, &entry.pinyin))
+                    .map(|entry| entry.traditional.clone())
+            });
+            return Ok(json!({
+                "simplified": simplified,
+                "traditional": traditional,
+                "pinyin": pinyin,
+                "definitions": definition,
+            }));
+        }
+
+        let candidates: Vec<&DictEntry> = self
+            .entries(&simplified)
+            .filter(|entry| pinyin.as_ref().is_none_or(|pinyin| pinyin_matches(pinyin, &entry.pinyin)))
+            .collect();
+        if candidates.is_empty() {
+            let message = match &pinyin {
+                Some(pinyin) => format!("No dictionary reading of {} matches {}", simplified, pinyin),
+                None => format!("{} is not in the dictionary", simplified),
+            };
+            return Err(row_error(row, RowErrorKind::UnknownWord, message));
+        }
+
+        // Distinct readings, in dictionary order
+        let mut readings: Vec<&str> = Vec::new();
+        for entry in &candidates {
+            if !readings.iter().any(|reading| reading.eq_ignore_ascii_case(&entry.pinyin)) {
+                readings.push(&entry.pinyin);
+            }
+        }
+        if readings.len() > 1 {
+            let mut error = row_error(
+                row,
+                RowErrorKind::AmbiguousReading,
+                format!("{} has {} readings, add a pinyin column to choose one", simplified, readings.len()),
+            );
+            error.readings = readings.iter().map(|reading| reading.to_string()).collect();
+            return Err(error);
+        }
+
+        // Merge the definitions of every entry with that reading
+        let definitions: Vec<&str> = candidates
+            .iter()
+            .flat_map(|entry| entry.definitions.iter().map(String::as_str))
+            .collect();
+
+        Ok(json!({
+            "simplified": simplified,
+            "traditional": traditional.unwrap_or_else(|| candidates[0].traditional.clone()),
+            "pinyin": pinyin.unwrap_or_else(|| readings[0].to_string()),
+            "definitions": definition.map_or_else(|| json!(definitions), Value::String),
+        }))
+    }
+
+    fn entries(&self, headword: &str) -> impl Iterator<Item = &'a DictEntry> + use<'a> {
+        let (dict, index) = (self.dict, self.index);
+        index
+            .lookup_headword(headword)
+            .iter()
+            .map(move |&id| &dict[id as usize])
+    }
+}
+
+/// Compares pinyin exactly when the file gives tone numbers, otherwise
+/// ignoring tones
+pub(crate) fn pinyin_matches(given: &str, entry: &str) -> bool {
+    let normalize = |pinyin: &str| -> String {
+        pinyin
+            .to_lowercase()
+            .replace("u:", "v")
+            .replace('ü', "v")
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect()
+    };
+
+    if given.chars().any(|c| c.is_ascii_digit()) {
+        normalize(given) == normalize(entry)
+    } else {
+        SearchEngine::remove_tones(given) == SearchEngine::remove_tones(entry)
+    }
+}
+
+fn row_error(row: &WordListRow, kind: RowErrorKind, message: String) -> RowError {
+    RowError {
+        row: row.line,
+        kind,
+        message,
+        readings: Vec::new(),
+    }
+}
@@ -42,6 +42,10 @@ pub async fn handle_login(
         })?;
 
     match user {
+        Some(user) if user.is_system => {
+            log::warn!("Login attempt as system user: {}", form.email);
+            Err(LoginError::InvalidCredentials)
+        },
         Some(user) => {
             let is_valid = UserRepository::verify_password(&user.password, &form.password)
                 .map_err(|e| {
@@ -12,7 +12,8 @@ use crate::{
     DbPool,
     utils::{set_user_session, render_template},
     data::repositories::UserRepository,
-    data::models::{RegisterError, RegisterForm}
+    data::models::{RegisterError, RegisterForm},
+    features::hsk::SYSTEM_USER_EMAIL,
 };
 
 pub async fn show_register_form(
@@ -37,7 +38,8 @@ pub async fn handle_register(
             RegisterError::SessionError("Failed to get DB connection".into())
         })?;
 
-    if UserRepository::email_exists(&mut conn, &form.email)? {
+    // The system account's address is reserved even before `import-hsk` creates it
+    if form.email.eq_ignore_ascii_case(SYSTEM_USER_EMAIL) || UserRepository::email_exists(&mut conn, &form.email)? {
         log::warn!("Registration attempt with existing email: {}", form.email);
         return Err(RegisterError::EmailTaken);
     }
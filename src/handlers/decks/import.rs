@@ -0,0 +1,330 @@
+use axum::extract::{Json, Multipart, State};
+use diesel::prelude::*;
+
+use crate::{
+    DbPool,
+    deck,
+    data::models::{
+        AnkiError, AnkiImportOptions, AnkiPreview, AppError, ImportResult, RowError,
+        RowErrorKind, SubtitleDeckRequest, SubtitleError, SubtitlePreview, WordListImportOptions,
+        WordListImportResult, WordListPreview,
+    },
+    features::anki::AnkiPackage,
+    features::dictionary::DictionaryHandle,
+    features::srs::SrsEngine,
+    features::subtitles::{SubtitleFormat, SubtitleParser, VocabularyMiner},
+    features::wordlist::{WordListParser, WordResolver},
+    utils::{self, CurrentUser},
+};
+
+/// Largest file accepted by the import endpoints
+pub const MAX_UPLOAD_BYTES: usize = 20 * 1024 * 1024;
+/// Rows returned by the word list preview
+const PREVIEW_ROWS: usize = 10;
+
+type ImportState = (DbPool, DictionaryHandle);
+
+/// Parses an uploaded subtitle file and returns its vocabulary ranked by frequency
+pub async fn preview_subtitle_import(
+    State((_pool, dictionary)): State<ImportState>,
+    _user: CurrentUser,
+    multipart: Multipart,
+) -> Result<Json<SubtitlePreview>, AppError> {
+    let upload = read_upload(multipart).await?;
+    let (file_name, bytes) = (upload.file_name, upload.bytes);
+
+    let content = SubtitleParser::decode(&bytes)?;
+    let format = SubtitleFormat::detect(&file_name, &content)
+        .ok_or(SubtitleError::UnsupportedFormat)?;
+    let lines = SubtitleParser::parse(&content, format)?;
+
+    let dict = dictionary.current();
+    let words = VocabularyMiner::mine(&lines, &file_name, &dict.entries, &dict.index);
+
+    Ok(Json(SubtitlePreview {
+        source: file_name,
+        line_count: lines.len(),
+        words,
+    }))
+}
+
+/// Creates a deck from the words selected in a subtitle preview
+pub async fn import_subtitle_deck(
+    State((pool, _dictionary)): State<ImportState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<SubtitleDeckRequest>,
+) -> Result<Json<ImportResult>, AppError> {
+    if payload.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Deck name is required".to_string()));
+    }
+    if payload.words.is_empty() {
+        return Err(AppError::BadRequest("No words selected".to_string()));
+    }
+
+    let mut conn = pool.get()?;
+
+    // Create the deck and add every word atomically
+    let result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let deck_id = deck::create_deck_internal(conn, user_id, payload.name.trim(), payload.privacy_value)?;
+        let mut imported = 0;
+        let mut skipped = 0;
+
+        for selection in payload.words {
+            match deck::add_word_to_deck_internal(conn, deck_id, selection.word_data) {
+                Ok(word_id) => {
+                    if let Some(context) = &selection.context {
+                        deck::set_word_context(conn, deck_id, word_id, context)?;
+                    }
+                    imported += 1;
+                }
+                // The same word selected twice
+                Err(diesel::result::Error::DatabaseError(
+                    diesel::result::DatabaseErrorKind::UniqueViolation,
+                    _,
+                )) => skipped += 1,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(ImportResult { deck_id, imported, skipped })
+    })
+    .map_err(|e| match e {
+        diesel::result::Error::DeserializationError(e) => {
+            AppError::BadRequest(format!("Invalid word data: {}", e))
+        }
+        e => AppError::Database(e),
+    })?;
+
+    Ok(Json(result))
+}
+
+/// Lists the note types, fields and decks of an uploaded Anki package
+pub async fn preview_anki_import(
+    State(_state): State<ImportState>,
+    _user: CurrentUser,
+    multipart: Multipart,
+) -> Result<Json<AnkiPreview>, AppError> {
+    let upload = read_upload(multipart).await?;
+    let package = AnkiPackage::read(&upload.bytes)?;
+
+    Ok(Json(package.preview()))
+}
+
+/// Creates a deck from an Anki package using the field mapping in the
+/// `options` form field, carrying review intervals over unless reset
+pub async fn import_anki_deck(
+    State((pool, dictionary)): State<ImportState>,
+    CurrentUser(user_id): CurrentUser,
+    multipart: Multipart,
+) -> Result<Json<ImportResult>, AppError> {
+    let upload = read_upload(multipart).await?;
+    let options: AnkiImportOptions = upload
+        .options
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("Missing options field".to_string()))
+        .and_then(|options| {
+            serde_json::from_str(options)
+                .map_err(|e| AppError::BadRequest(format!("Invalid options: {}", e)))
+        })?;
+
+    if options.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Deck name is required".to_string()));
+    }
+
+    let package = AnkiPackage::read(&upload.bytes)?;
+    let note_type_id = options
+        .note_type_id
+        .or_else(|| package.default_note_type())
+        .ok_or_else(|| AnkiError::InvalidCollection("no note types".to_string()))?;
+    let dict = dictionary.current();
+    let (notes, unresolved) = package
+        .map_notes(note_type_id, &options.mapping, &dict.entries, &dict.index, !options.reset_scheduling)?;
+
+    if notes.is_empty() {
+        return Err(AppError::BadRequest("No notes could be mapped to words".to_string()));
+    }
+
+    let mut conn = pool.get()?;
+
+    // Create the deck, add every word and seed its schedule atomically
+    let result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let deck_id = deck::create_deck_internal(conn, user_id, options.name.trim(), options.privacy_value)?;
+        let mut imported = 0;
+        let mut skipped = unresolved;
+
+        for note in notes {
+            match deck::add_word_to_deck_internal(conn, deck_id, note.word_data) {
+                Ok(word_id) => {
+                    if let Some(schedule) = &note.schedule {
+                        SrsEngine::new(conn).import_schedule(user_id, deck_id, word_id, schedule)?;
+                    }
+                    imported += 1;
+                }
+                // Notes sharing the same word
+                Err(diesel::result::Error::DatabaseError(
+                    diesel::result::DatabaseErrorKind::UniqueViolation,
+                    _,
+                )) => skipped += 1,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(ImportResult { deck_id, imported, skipped })
+    })?;
+
+    Ok(Json(result))
+}
+
+/// Detects the delimiter of an uploaded CSV/TSV word list and returns its
+/// first rows so the user can map columns
+pub async fn preview_word_list_import(
+    State(_state): State<ImportState>,
+    _user: CurrentUser,
+    multipart: Multipart,
+) -> Result<Json<WordListPreview>, AppError> {
+    let upload = read_upload(multipart).await?;
+    let content = WordListParser::decode(&upload.bytes)?;
+    let delimiter = WordListParser::detect_delimiter(&upload.file_name, &content);
+    let rows = WordListParser::parse(&content, delimiter)?;
+
+    Ok(Json(WordListPreview {
+        delimiter: (delimiter as char).to_string(),
+        column_count: rows.iter().map(|row| row.cells.len()).max().unwrap_or(0),
+        row_count: rows.len(),
+        rows: rows.into_iter().take(PREVIEW_ROWS).map(|row| row.cells).collect(),
+    }))
+}
+
+/// Adds every row of a CSV/TSV word list to a new or existing deck in one
+/// transaction, reporting the rows that could not be imported
+pub async fn import_word_list(
+    State((pool, dictionary)): State<ImportState>,
+    CurrentUser(user_id): CurrentUser,
+    multipart: Multipart,
+) -> Result<Json<WordListImportResult>, AppError> {
+    let upload = read_upload(multipart).await?;
+    let options: WordListImportOptions = upload
+        .options
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("Missing options field".to_string()))
+        .and_then(|options| {
+            serde_json::from_str(options)
+                .map_err(|e| AppError::BadRequest(format!("Invalid options: {}", e)))
+        })?;
+
+    let new_deck_name = match (options.deck_id, options.name.as_deref().map(str::trim)) {
+        (Some(_), _) => None,
+        (None, Some(name)) if !name.is_empty() => Some(name.to_string()),
+        (None, _) => return Err(AppError::BadRequest("Deck name is required".to_string())),
+    };
+
+    // Parse and resolve every row before touching the database
+    let content = WordListParser::decode(&upload.bytes)?;
+    let delimiter = match options.delimiter {
+        Some(delimiter) if delimiter.is_ascii() => delimiter as u8,
+        Some(_) => return Err(AppError::BadRequest("Delimiter must be ASCII".to_string())),
+        None => WordListParser::detect_delimiter(&upload.file_name, &content),
+    };
+    let mut rows = WordListParser::parse(&content, delimiter)?;
+    if options.has_header {
+        rows.remove(0);
+    }
+    WordResolver::validate_columns(&options.columns, &rows)?;
+
+    let dict = dictionary.current();
+    let resolver = WordResolver::new(&dict.entries, &dict.index);
+    let mut errors = Vec::new();
+    let mut resolved = Vec::new();
+    for row in &rows {
+        match resolver.resolve(row, &options.columns) {
+            Ok(word_data) => resolved.push((row.line, word_data)),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    let mut conn = pool.get()?;
+
+    // Verify the target deck belongs to this user
+    if let Some(deck_id) = options.deck_id {
+        utils::verify_deck_owner(&mut conn, deck_id, user_id)?;
+    }
+
+    // Add every resolved row atomically
+    let (deck_id, imported) = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let deck_id = match (options.deck_id, &new_deck_name) {
+                (Some(deck_id), _) => deck_id,
+                (None, name) => deck::create_deck_internal(
+                    conn,
+                    user_id,
+                    name.as_deref().unwrap_or_default(),
+                    options.privacy_value,
+                )?,
+            };
+            let mut imported = 0;
+
+            for (line, word_data) in resolved {
+                match deck::add_word_to_deck_internal(conn, deck_id, word_data) {
+                    Ok(_) => imported += 1,
+                    Err(diesel::result::Error::DatabaseError(
+                        diesel::result::DatabaseErrorKind::UniqueViolation,
+                        _,
+                    )) => errors.push(RowError {
+                        row: line,
+                        kind: RowErrorKind::Duplicate,
+                        message: "Word is already in the deck".to_string(),
+                        readings: Vec::new(),
+                    }),
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok((deck_id, imported))
+        })?;
+
+    errors.sort_by_key(|error| error.row);
+    Ok(Json(WordListImportResult { deck_id, imported, errors }))
+}
+
+/// Parts of a multipart upload used by the import endpoints
+struct Upload {
+    file_name: String,
+    bytes: Vec<u8>,
+    options: Option<String>, // JSON `options` field, if sent
+}
+
+/// Reads the `file` and optional `options` fields of a multipart upload
+async fn read_upload(mut multipart: Multipart) -> Result<Upload, AppError> {
+    let mut file = None;
+    let mut options = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Invalid upload: {}", e)))?
+    {
+        match field.name() {
+            Some("file") => {
+                let file_name = field.file_name().unwrap_or("upload").to_string();
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("Invalid upload: {}", e)))?;
+                file = Some((file_name, bytes.to_vec()));
+            }
+            Some("options") => {
+                options = Some(
+                    field
+                        .text()
+                        .await
+                        .map_err(|e| AppError::BadRequest(format!("Invalid upload: {}", e)))?,
+                );
+            }
+            _ => continue,
+        }
+    }
+
+    let (file_name, bytes) =
+        file.ok_or_else(|| AppError::BadRequest("Missing file field".to_string()))?;
+    Ok(Upload { file_name, bytes, options })
+}
@@ -0,0 +1,3 @@
+pub mod import;
+pub mod public;
+pub mod tags;
\ No newline at end of file
@@ -0,0 +1,425 @@
+use axum::extract::{Json, Path, Query, State};
+use chrono::Utc;
+use diesel::dsl::{count, sql};
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    DbPool,
+    deck,
+    handlers::decks::tags,
+    data::models::{
+        ApiResponse, AppError, ClonedDeck, DeckLikeStatus, DeckWithWords, LikedDeck, PublicDeckList,
+        PinyinQuery, PublicDeckQuery, PublicDeckSort, PublicDeckSummary, SubscribedDeck,
+    },
+    schema::{deck_likes, deck_subscriptions, deck_tags, deck_words, decks},
+    utils::{self, CurrentUser},
+};
+
+/// Page size when none is requested
+const DEFAULT_PER_PAGE: i64 = 20;
+/// Largest page size accepted
+const MAX_PER_PAGE: i64 = 100;
+/// Window counted when sorting by recent likes
+const RECENT_LIKES_DAYS: i64 = 7;
+
+/// Lists public decks, filtered by name, tag, size and likes
+pub async fn list_public_decks(
+    Query(params): Query<PublicDeckQuery>,
+    State(pool): State<DbPool>,
+    user: Option<CurrentUser>,
+) -> Result<Json<PublicDeckList>, AppError> {
+    let user_id = user.map(|CurrentUser(user_id)| user_id);
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+
+    let mut conn = pool.get()?;
+
+    let word_count = count(deck_words::word_id.nullable());
+    let mut query = decks::table
+        .left_join(deck_words::table)
+        .filter(decks::privacy_value.eq(true))
+        .group_by(decks::deck_id)
+        .select((
+            decks::deck_id,
+            decks::deck_name,
+            decks::like_count,
+            decks::user_made,
+            decks::duplicated_check,
+            word_count,
+        ))
+        .into_boxed();
+
+    if let Some(q) = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        query = query.filter(
+            decks::deck_name
+                .like(format!("%{}%", utils::escape_like(q)))
+                .escape('\\'),
+        );
+    }
+    // Decks must carry every requested tag
+    for tag in tags::parse_tag_filter(params.tags.as_deref()) {
+        let tagged = deck_tags::table
+            .filter(deck_tags::tag.eq(tag))
+            .select(deck_tags::deck_id);
+        query = query.filter(decks::deck_id.eq_any(tagged));
+    }
+    if let Some(min_likes) = params.min_likes {
+        query = query.filter(decks::like_count.ge(min_likes));
+    }
+    if let Some(min_words) = params.min_words {
+        query = query.having(word_count.ge(min_words));
+    }
+    if let Some(max_words) = params.max_words {
+        query = query.having(word_count.le(max_words));
+    }
+
+    query = match params.sort {
+        PublicDeckSort::Likes => query.order((decks::like_count.desc(), decks::deck_id.desc())),
+        PublicDeckSort::Newest => query.order(decks::deck_id.desc()),
+        PublicDeckSort::Name => query.order((decks::deck_name.asc(), decks::deck_id.asc())),
+        PublicDeckSort::Size => query.order((word_count.desc(), decks::deck_id.desc())),
+        PublicDeckSort::RecentLikes => {
+            let recent_likes = sql::<BigInt>(&format!(
+                "(SELECT COUNT(*) FROM deck_likes \
+                 WHERE deck_likes.deck_id = decks.deck_id \
+                 AND deck_likes.liked_at >= datetime('now', '-{} days'))",
+                RECENT_LIKES_DAYS
+            ));
+            query.order((recent_likes.desc(), decks::like_count.desc(), decks::deck_id.desc()))
+        }
+    };
+
+    // Fetch one extra row to know whether another page exists
+    let mut rows = query
+        .offset((page - 1) * per_page)
+        .limit(per_page + 1)
+        .load::<(i32, String, i32, bool, bool, i64)>(&mut conn)?;
+    let has_more = rows.len() as i64 > per_page;
+    rows.truncate(per_page as usize);
+
+    // Tags of the decks on this page
+    let deck_ids: Vec<i32> = rows.iter().map(|row| row.0).collect();
+    let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+    for (deck_id, tag) in deck_tags::table
+        .filter(deck_tags::deck_id.eq_any(&deck_ids))
+        .order(deck_tags::tag.asc())
+        .load::<(i32, String)>(&mut conn)?
+    {
+        tags.entry(deck_id).or_default().push(tag);
+    }
+
+    // Decks on this page the current user has liked
+    let liked: HashSet<i32> = match user_id {
+        Some(user_id) => deck_likes::table
+            .filter(deck_likes::user_id.eq(user_id))
+            .filter(deck_likes::deck_id.eq_any(&deck_ids))
+            .select(deck_likes::deck_id)
+            .load::<i32>(&mut conn)?
+            .into_iter()
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    let decks = rows
+        .into_iter()
+        .map(|(id, name, like_count, user_made, duplicated_check, word_count)| PublicDeckSummary {
+            id,
+            name,
+            word_count,
+            like_count,
+            tags: tags.remove(&id).unwrap_or_default(),
+            user_made,
+            is_clone: duplicated_check,
+            liked: liked.contains(&id),
+        })
+        .collect();
+
+    Ok(Json(PublicDeckList { decks, page, per_page, has_more }))
+}
+
+/// Shows the words of a public deck to anyone
+pub async fn view_public_deck(
+    Path(deck_id): Path<i32>,
+    Query(display): Query<PinyinQuery>,
+    State(pool): State<DbPool>,
+) -> Result<Json<DeckWithWords>, AppError> {
+    let mut conn = pool.get()?;
+
+    // Get basic deck info
+    let (id, name, description, cover_word_id): (i32, String, Option<String>, Option<i32>) = decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .filter(decks::privacy_value.eq(true))
+        .select((decks::deck_id, decks::deck_name, decks::description, decks::cover_word_id))
+        .first(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Public deck not found".to_string()))?;
+
+    let mut words = deck::load_deck_words(&mut conn, deck_id, false)?;
+    if let Some(format) = display.pinyin {
+        words.iter_mut().for_each(|word| word.format_pinyin(format));
+    }
+
+    Ok(Json(DeckWithWords { id, name, description, cover_word_id, words }))
+}
+
+/// Copies a public deck, its words and tags into the current user's account
+/// as a private deck marked as a clone of the original
+pub async fn clone_public_deck(
+    Path(deck_id): Path<i32>,
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<ClonedDeck>, AppError> {
+    let mut conn = pool.get()?;
+
+    let (name, owner, description, cover_word_id): (String, i32, Option<String>, Option<i32>) = decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .filter(decks::privacy_value.eq(true))
+        .select((decks::deck_name, decks::user_id, decks::description, decks::cover_word_id))
+        .first(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Public deck not found".to_string()))?;
+
+    if owner == user_id {
+        return Err(AppError::BadRequest("You already own this deck".to_string()));
+    }
+
+    let cloned = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let clone_id = deck::create_deck_internal(conn, user_id, &name, false)?;
+            diesel::update(decks::table.filter(decks::deck_id.eq(clone_id)))
+                .set((
+                    decks::duplicated_check.eq(true),
+                    decks::source_deck_id.eq(deck_id),
+                    decks::description.eq(description),
+                    decks::cover_word_id.eq(cover_word_id),
+                ))
+                .execute(conn)?;
+
+            // Deck notes stay with their author, the clone starts without them
+            let word_count = diesel::insert_into(deck_words::table)
+                .values(
+                    deck_words::table
+                        .filter(deck_words::deck_id.eq(deck_id))
+                        .select((
+                            clone_id.into_sql::<diesel::sql_types::Integer>(),
+                            deck_words::word_id,
+                            deck_words::context_line,
+                            deck_words::context_source,
+                            deck_words::context_timestamp_ms,
+                            deck_words::position,
+                        )),
+                )
+                .into_columns((
+                    deck_words::deck_id,
+                    deck_words::word_id,
+                    deck_words::context_line,
+                    deck_words::context_source,
+                    deck_words::context_timestamp_ms,
+                    deck_words::position,
+                ))
+                .execute(conn)?;
+
+            diesel::insert_into(deck_tags::table)
+                .values(
+                    deck_tags::table
+                        .filter(deck_tags::deck_id.eq(deck_id))
+                        .select((clone_id.into_sql::<diesel::sql_types::Integer>(), deck_tags::tag)),
+                )
+                .into_columns((deck_tags::deck_id, deck_tags::tag))
+                .execute(conn)?;
+
+            Ok(ClonedDeck {
+                deck_id: clone_id,
+                source_deck_id: deck_id,
+                word_count,
+            })
+        })?;
+
+    Ok(Json(cloned))
+}
+
+
+/// Likes a public deck, once per user
+pub async fn like_deck(
+    Path(deck_id): Path<i32>,
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<DeckLikeStatus>, AppError> {
+    let mut conn = pool.get()?;
+
+    // Only public decks can be liked
+    decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .filter(decks::privacy_value.eq(true))
+        .select(decks::deck_id)
+        .first::<i32>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Public deck not found".to_string()))?;
+
+    let like_count = set_deck_like(&mut conn, user_id, deck_id, true)?;
+
+    Ok(Json(DeckLikeStatus { deck_id, liked: true, like_count }))
+}
+
+/// Removes the current user's like from a deck
+pub async fn unlike_deck(
+    Path(deck_id): Path<i32>,
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<DeckLikeStatus>, AppError> {
+    let mut conn = pool.get()?;
+
+    // A deck made private since it was liked can still be unliked
+    decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .select(decks::deck_id)
+        .first::<i32>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Deck not found".to_string()))?;
+
+    let like_count = set_deck_like(&mut conn, user_id, deck_id, false)?;
+
+    Ok(Json(DeckLikeStatus { deck_id, liked: false, like_count }))
+}
+
+/// Lists the public decks the current user has liked, most recent first
+pub async fn list_liked_decks(
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<LikedDeck>>, AppError> {
+    let mut conn = pool.get()?;
+
+    let liked = deck_likes::table
+        .inner_join(decks::table)
+        .filter(deck_likes::user_id.eq(user_id))
+        .filter(decks::privacy_value.eq(true))
+        .order((deck_likes::liked_at.desc(), decks::deck_id.desc()))
+        .select((decks::deck_id, decks::deck_name, decks::like_count, deck_likes::liked_at))
+        .load::<LikedDeck>(&mut conn)?;
+
+    Ok(Json(liked))
+}
+
+/// Subscribes to a public deck so it can be studied without cloning it
+pub async fn subscribe_deck(
+    Path(deck_id): Path<i32>,
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
+
+    let owner: i32 = decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .filter(decks::privacy_value.eq(true))
+        .select(decks::user_id)
+        .first(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Public deck not found".to_string()))?;
+
+    if owner == user_id {
+        return Err(AppError::BadRequest("You already own this deck".to_string()));
+    }
+
+    diesel::insert_into(deck_subscriptions::table)
+        .values((
+            deck_subscriptions::user_id.eq(user_id),
+            deck_subscriptions::deck_id.eq(deck_id),
+            deck_subscriptions::subscribed_at.eq(Utc::now().naive_utc()),
+        ))
+        .on_conflict_do_nothing()
+        .execute(&mut conn)?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Subscribed to deck".to_string(),
+    }))
+}
+
+/// Ends a subscription. Review history for the deck is kept.
+pub async fn unsubscribe_deck(
+    Path(deck_id): Path<i32>,
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<ApiResponse>, AppError> {
+    let mut conn = pool.get()?;
+
+    let deleted = diesel::delete(
+        deck_subscriptions::table
+            .filter(deck_subscriptions::user_id.eq(user_id))
+            .filter(deck_subscriptions::deck_id.eq(deck_id)),
+    )
+    .execute(&mut conn)?;
+
+    if deleted == 0 {
+        return Err(AppError::NotFound("Not subscribed to this deck".to_string()));
+    }
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Unsubscribed from deck".to_string(),
+    }))
+}
+
+/// Lists the public decks the current user is subscribed to
+pub async fn list_subscribed_decks(
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<SubscribedDeck>>, AppError> {
+    let mut conn = pool.get()?;
+
+    let subscribed = deck_subscriptions::table
+        .inner_join(decks::table)
+        .filter(deck_subscriptions::user_id.eq(user_id))
+        .filter(decks::privacy_value.eq(true))
+        .order((decks::user_made.asc(), decks::deck_name.asc()))
+        .select((decks::deck_id, decks::deck_name, decks::user_made, deck_subscriptions::subscribed_at))
+        .load::<SubscribedDeck>(&mut conn)?;
+
+    Ok(Json(subscribed))
+}
+
+/// Adds or removes a like and adjusts `decks.like_count` in the same
+/// transaction, so repeating either request leaves the count unchanged.
+/// Returns the new like count.
+fn set_deck_like(
+    conn: &mut SqliteConnection,
+    user_id: i32,
+    deck_id: i32,
+    liked: bool,
+) -> Result<i32, diesel::result::Error> {
+    conn.transaction(|conn| {
+        let deck = decks::table.filter(decks::deck_id.eq(deck_id));
+
+        if liked {
+            let inserted = diesel::insert_into(deck_likes::table)
+                .values((
+                    deck_likes::user_id.eq(user_id),
+                    deck_likes::deck_id.eq(deck_id),
+                    deck_likes::liked_at.eq(Utc::now().naive_utc()),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            if inserted > 0 {
+                diesel::update(deck)
+                    .set(decks::like_count.eq(decks::like_count + 1))
+                    .execute(conn)?;
+            }
+        } else {
+            let deleted = diesel::delete(
+                deck_likes::table
+                    .filter(deck_likes::user_id.eq(user_id))
+                    .filter(deck_likes::deck_id.eq(deck_id)),
+            )
+            .execute(conn)?;
+            if deleted > 0 {
+                diesel::update(deck.filter(decks::like_count.gt(0)))
+                    .set(decks::like_count.eq(decks::like_count - 1))
+                    .execute(conn)?;
+            }
+        }
+
+        deck.select(decks::like_count).first(conn)
+    })
+}
@@ -0,0 +1,195 @@
+use axum::extract::{Json, Path, Query, State};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use std::collections::BTreeSet;
+
+use crate::{
+    DbPool,
+    data::models::{
+        AppError, DeckTagError, DeckTags, DeckTagsRequest, MAX_TAG_LENGTH, MAX_TAGS_PER_DECK, TagCount,
+        TagSuggestQuery,
+    },
+    schema::{deck_tags, decks},
+    utils::{self, CurrentUser, OwnedDeck},
+};
+
+/// Suggestions returned when no limit is requested
+const DEFAULT_SUGGESTIONS: i64 = 10;
+/// Most suggestions returned at once
+const MAX_SUGGESTIONS: i64 = 50;
+
+/// Lowercases a tag and collapses runs of whitespace into single spaces
+fn clean_tag(raw: &str) -> String {
+    raw.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Normalizes a tag for storage, rejecting empty, overlong or comma-separated tags
+pub(crate) fn normalize_tag(raw: &str) -> Result<String, DeckTagError> {
+    let tag = clean_tag(raw);
+    if tag.is_empty() {
+        Err(DeckTagError::Empty)
+    } else if tag.contains(',') {
+        Err(DeckTagError::Comma)
+    } else if tag.contains(['<', '>', '&', '"', '\'']) {
+        Err(DeckTagError::Markup)
+    } else if tag.chars().count() > MAX_TAG_LENGTH {
+        Err(DeckTagError::TooLong)
+    } else {
+        Ok(tag)
+    }
+}
+
+/// Parses a comma-separated tag filter such as `hsk 1, grammar`
+pub(crate) fn parse_tag_filter(raw: Option<&str>) -> Vec<String> {
+    let tags: BTreeSet<String> = raw
+        .into_iter()
+        .flat_map(|raw| raw.split(','))
+        .map(clean_tag)
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.into_iter().collect()
+}
+
+/// Loads the tags of a deck in alphabetical order
+pub(crate) fn load_deck_tags(
+    conn: &mut SqliteConnection,
+    deck_id: i32,
+) -> Result<Vec<String>, diesel::result::Error> {
+    deck_tags::table
+        .filter(deck_tags::deck_id.eq(deck_id))
+        .select(deck_tags::tag)
+        .order(deck_tags::tag.asc())
+        .load(conn)
+}
+
+/// Lists the tags of one of the user's decks or of a public deck
+pub async fn list_deck_tags(
+    Path(deck_id): Path<i32>,
+    State(pool): State<DbPool>,
+    user: Option<CurrentUser>,
+) -> Result<Json<DeckTags>, AppError> {
+    let user_id = user.map(|CurrentUser(user_id)| user_id);
+
+    let mut conn = pool.get()?;
+
+    // Verify the deck is public or belongs to this user
+    let (owner, public): (i32, bool) = decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .select((decks::user_id, decks::privacy_value))
+        .first(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Deck not found".to_string()))?;
+
+    if !public && user_id != Some(owner) {
+        return Err(AppError::Forbidden("Access denied".to_string()));
+    }
+
+    let tags = load_deck_tags(&mut conn, deck_id)?;
+
+    Ok(Json(DeckTags { deck_id, tags }))
+}
+
+/// Adds tags to one of the user's decks, keeping any it already has
+pub async fn add_deck_tags(
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+    Json(payload): Json<DeckTagsRequest>,
+) -> Result<Json<DeckTags>, AppError> {
+    let new_tags = payload
+        .tags
+        .iter()
+        .map(|tag| normalize_tag(tag))
+        .collect::<Result<BTreeSet<String>, _>>()?;
+
+    let mut conn = pool.get()?;
+
+    // Check the limit against the tags already stored before inserting
+    let tags = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let mut tags: BTreeSet<String> = load_deck_tags(conn, deck_id)?.into_iter().collect();
+            let added: Vec<&String> = new_tags.iter().filter(|tag| !tags.contains(*tag)).collect();
+            if tags.len() + added.len() > MAX_TAGS_PER_DECK {
+                return Ok(None);
+            }
+
+            let rows: Vec<_> = added
+                .iter()
+                .map(|tag| (deck_tags::deck_id.eq(deck_id), deck_tags::tag.eq(tag.as_str())))
+                .collect();
+            diesel::insert_into(deck_tags::table)
+                .values(&rows)
+                .execute(conn)?;
+
+            tags.extend(added.into_iter().cloned());
+            Ok(Some(tags.into_iter().collect()))
+        })?
+        .ok_or(AppError::from(DeckTagError::TooMany))?;
+
+    Ok(Json(DeckTags { deck_id, tags }))
+}
+
+/// Removes a tag from one of the user's decks
+pub async fn remove_deck_tag(
+    Path((_, tag)): Path<(i32, String)>,
+    State(pool): State<DbPool>,
+    OwnedDeck { deck_id, .. }: OwnedDeck,
+) -> Result<Json<DeckTags>, AppError> {
+    let mut conn = pool.get()?;
+
+    let deleted = diesel::delete(
+        deck_tags::table
+            .filter(deck_tags::deck_id.eq(deck_id))
+            .filter(deck_tags::tag.eq(clean_tag(&tag))),
+    )
+    .execute(&mut conn)?;
+
+    if deleted == 0 {
+        return Err(AppError::NotFound("Tag not found".to_string()));
+    }
+
+    let tags = load_deck_tags(&mut conn, deck_id)?;
+
+    Ok(Json(DeckTags { deck_id, tags }))
+}
+
+/// Suggests tags starting with a prefix, most used first. Counts public decks
+/// and, for a logged in user, their own private decks.
+pub async fn suggest_tags(
+    Query(params): Query<TagSuggestQuery>,
+    State(pool): State<DbPool>,
+    user: Option<CurrentUser>,
+) -> Result<Json<Vec<TagCount>>, AppError> {
+    let user_id = user.map(|CurrentUser(user_id)| user_id);
+    let limit = params.limit.unwrap_or(DEFAULT_SUGGESTIONS).clamp(1, MAX_SUGGESTIONS);
+
+    let mut conn = pool.get()?;
+
+    let mut query = deck_tags::table
+        .inner_join(decks::table)
+        .group_by(deck_tags::tag)
+        .select((deck_tags::tag, count_star()))
+        .order((count_star().desc(), deck_tags::tag.asc()))
+        .limit(limit)
+        .into_boxed();
+
+    query = match user_id {
+        Some(user_id) => query.filter(decks::privacy_value.eq(true).or(decks::user_id.eq(user_id))),
+        None => query.filter(decks::privacy_value.eq(true)),
+    };
+
+    let prefix = clean_tag(params.q.as_deref().unwrap_or_default());
+    if !prefix.is_empty() {
+        query = query.filter(
+            deck_tags::tag
+                .like(format!("{}%", utils::escape_like(&prefix)))
+                .escape('\\'),
+        );
+    }
+
+    let suggestions = query.load::<TagCount>(&mut conn)?;
+
+    Ok(Json(suggestions))
+}
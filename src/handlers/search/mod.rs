@@ -1 +1,3 @@
-pub mod search;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod search;
+pub mod segment;
\ No newline at end of file
@@ -9,9 +9,8 @@ use tera::Context;
 use crate::{
     data::models::*,
     utils::{self, render_template},
-    features::search::SearchEngine
+    features::{dictionary::DictionaryHandle, search::SearchEngine}
 };
-use crate::data::models::DictEntry;
 
 // Handler for HTML page
 pub async fn search_page(
@@ -28,16 +27,30 @@ pub async fn search_page(
 // Slimmed-down API handler
 pub async fn search_api(
     Query(params): Query<SearchParams>,
-    State((_pool, dict)): State<(crate::DbPool, Arc<Vec<DictEntry>>)>
+    Query(display): Query<PinyinQuery>,
+    State((_pool, dictionary)): State<(crate::DbPool, DictionaryHandle)>
 ) -> Json<SearchResult> {
-    let results = SearchEngine::search_entries(&params.q, &dict, params.lang.as_deref())
+    let dict = dictionary.current();
+    let (interpretations, results) = SearchEngine::search(&params.q, &dict.entries, &dict.index, params.lang.as_deref());
+    let results = results
         .into_iter()
         .take(15)
-        .map(|(entry, _)| entry)
+        .map(|(mut entry, _)| {
+            if let Some(format) = display.pinyin {
+                entry.format_pinyin(format);
+            }
+            entry
+        })
         .collect();
 
     Json(SearchResult {
         query: params.q,
+        interpretations,
         results,
     })
-}
\ No newline at end of file
+}
+
+// Header metadata of the loaded dictionary
+pub async fn dictionary_info(State(dictionary): State<DictionaryHandle>) -> Json<DictionaryInfo> {
+    Json(dictionary.current().info.clone())
+}
@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::{
+    data::models::*,
+    features::{dictionary::DictionaryHandle, search::Segmenter}
+};
+
+/// Longest text accepted by the segmentation endpoint, in characters
+const MAX_SEGMENT_CHARS: usize = 2000;
+
+// Splits a sentence into dictionary words with their entries
+pub async fn segment_api(
+    Query(params): Query<SegmentParams>,
+    Query(display): Query<PinyinQuery>,
+    State((_pool, dictionary)): State<(crate::DbPool, DictionaryHandle)>
+) -> Result<Json<SegmentResult>, AppError> {
+    if params.q.chars().count() > MAX_SEGMENT_CHARS {
+        return Err(AppError::invalid_field(
+            "q",
+            format!("Text must be at most {} characters", MAX_SEGMENT_CHARS),
+        ));
+    }
+
+    let dict = dictionary.current();
+    let mut tokens = Segmenter::segment(&params.q, &dict.entries, &dict.index);
+    if let Some(format) = display.pinyin {
+        tokens
+            .iter_mut()
+            .flat_map(|token| token.entries.iter_mut())
+            .for_each(|entry| entry.format_pinyin(format));
+    }
+
+    Ok(Json(SegmentResult {
+        query: params.q,
+        tokens,
+    }))
+}
@@ -0,0 +1,65 @@
+use axum::extract::{Json, State};
+use diesel::prelude::*;
+
+use crate::{
+    DbPool,
+    data::models::{AppError, SrsSettings, UpdateSrsSettingsRequest},
+    features::srs::SrsEngine,
+    schema::user_settings,
+    utils::CurrentUser,
+};
+
+/// Range accepted for the FSRS desired retention
+const RETENTION_RANGE: std::ops::RangeInclusive<f32> = 0.7..=0.99;
+
+/// Returns the scheduler and desired retention of the current user
+pub async fn get_srs_settings(
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<SrsSettings>, AppError> {
+    let mut conn = pool.get()?;
+
+    let settings = SrsEngine::new(&mut conn).get_user_settings(user_id)?;
+
+    Ok(Json(settings.into()))
+}
+
+/// Switches the current user's scheduler and/or desired retention
+pub async fn update_srs_settings(
+    State(pool): State<DbPool>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<UpdateSrsSettingsRequest>,
+) -> Result<Json<SrsSettings>, AppError> {
+    if let Some(retention) = payload.desired_retention
+        && !RETENTION_RANGE.contains(&retention)
+    {
+        return Err(AppError::invalid_field(
+            "desired_retention",
+            format!(
+                "Desired retention must be between {} and {}",
+                RETENTION_RANGE.start(),
+                RETENTION_RANGE.end()
+            ),
+        ));
+    }
+
+    let mut conn = pool.get()?;
+
+    // Merge the request into the current settings
+    let mut settings = SrsEngine::new(&mut conn).get_user_settings(user_id)?;
+    if let Some(scheduler) = payload.scheduler {
+        settings.scheduler = scheduler.as_str().to_string();
+    }
+    if let Some(retention) = payload.desired_retention {
+        settings.desired_retention = retention;
+    }
+
+    diesel::insert_into(user_settings::table)
+        .values(&settings)
+        .on_conflict(user_settings::user_id)
+        .do_update()
+        .set(&settings)
+        .execute(&mut conn)?;
+
+    Ok(Json(settings.into()))
+}
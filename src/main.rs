@@ -1,22 +1,24 @@
 use axum::{
     Router,
-    extract::{Extension, Path},
+    extract::{DefaultBodyLimit, Extension, Path},
     response::{IntoResponse, Redirect, Html},
     http::StatusCode,
     routing::{delete, get, get_service, post, put},
 };
 use data::*;
+use data::models::{AppConfig, MigrationError};
 use diesel::{
     SqliteConnection,
     r2d2::{ConnectionManager, Pool},
 };
-use handlers::{auth::*, search::*};
+use handlers::{auth::*, decks::*, search::*, study::*};
 use std::sync::Arc;
 use tera::Tera;
 use time::Duration;
 use tokio::net::TcpListener;
 use tower_http::services::ServeDir;
-use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
+use tower_sessions::{Expiry, SessionManagerLayer};
+use utils::CurrentUser;
 
 mod data;
 mod deck;
@@ -28,31 +30,86 @@ type DbPool = Pool<ConnectionManager<SqliteConnection>>;
 
 #[tokio::main]
 async fn main() {
-    // Database configuration
+    // Application configuration
     dotenv::dotenv().ok();
-    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://site.db".into());
+    let config = AppConfig::load().unwrap_or_else(|e| {
+        eprintln!("Configuration error: {}", e);
+        std::process::exit(1);
+    });
 
-    let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+    // Database configuration
+    let manager = ConnectionManager::<SqliteConnection>::new(&config.database.url);
     let pool = Pool::builder()
         .build(manager)
         .expect("Failed to create DB pool");
 
+    // Applies pending migrations and exits: `migrate`
+    let migrate_only = std::env::args().nth(1).as_deref() == Some("migrate");
+    let migrated = pool
+        .get()
+        .map_err(MigrationError::from)
+        .and_then(|mut conn| migrations::prepare(&mut conn, migrate_only || config.database.auto_migrate));
+    match migrated {
+        Ok(applied) if migrate_only && applied.is_empty() => println!("Database is up to date"),
+        Ok(applied) => applied.iter().for_each(|version| println!("Applied migration {}", version)),
+        Err(e) => {
+            eprintln!("Database migration error: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if migrate_only {
+        return;
+    }
+    if let Err(e) = config.validate_serving() {
+        eprintln!("Configuration error: {}", e);
+        std::process::exit(1);
+    }
+
     // Dictionary data loading
-    let dict_data = Arc::new(parsing::parse_cedict());
+    let dictionary = features::dictionary::Dictionary::load(&config.dictionary.path).unwrap_or_else(|e| {
+        eprintln!("Dictionary error: {}", e);
+        std::process::exit(1);
+    });
+    dictionary.report();
+
+    if std::env::args().nth(1).as_deref() == Some("bench-search") {
+        features::search::benchmark::run(&dictionary.entries, &dictionary.index);
+        return;
+    }
+
+    // Creates the HSK system decks: `import-hsk [path to complete.json]`
+    if std::env::args().nth(1).as_deref() == Some("import-hsk") {
+        let path = std::env::args()
+            .nth(2)
+            .unwrap_or_else(|| features::hsk::DEFAULT_LIST_PATH.to_string());
+        if let Err(e) = features::hsk::run(&pool, &dictionary.entries, &dictionary.index, std::path::Path::new(&path)) {
+            eprintln!("HSK import failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Reload with `kill -HUP` after replacing the dictionary file
+    let dictionary = features::dictionary::DictionaryHandle::new(config.dictionary.path.clone(), dictionary);
+    tokio::spawn(features::dictionary::reload_on_sighup(dictionary.clone()));
 
     // Templates configuration
-    let template_path = format!("{}/src/templates/**/*.html", env!("CARGO_MANIFEST_DIR"));
-    let templates = Tera::new(&template_path).unwrap_or_else(|e| {
+    let templates = Tera::new(&config.template_glob()).unwrap_or_else(|e| {
         eprintln!("Template parsing error: {}", e);
         std::process::exit(1);
     });
     let templates = Arc::new(templates);
     
     // Sessions configuration
-    let session_store = MemoryStore::default();
+    let session_store = repositories::SqliteSessionStore::new(pool.clone());
+    tokio::spawn(
+        session_store
+            .clone()
+            .continuously_delete_expired(std::time::Duration::from_secs(60 * 60)),
+    );
     let session_layer = SessionManagerLayer::new(session_store)
-        .with_expiry(Expiry::OnInactivity(Duration::days(1)))
-        .with_secure(false);
+        .with_expiry(Expiry::OnInactivity(Duration::days(config.session.expiry_days)))
+        .with_secure(config.session.secure);
 
     // Build routers
     let deck_api_router = Router::new()
@@ -63,22 +120,76 @@ async fn main() {
         .route("/create", post(deck::create_deck))
         .route("/add-word", post(deck::add_word_to_deck))
         .route("/{deck_id}/privacy", put(deck::update_deck_privacy))
+        .route("/{deck_id}/scheduler", put(deck::update_deck_scheduler))
+        .route("/{deck_id}/order", put(deck::reorder_deck_words))
+        .route("/{deck_id}/tags", get(tags::list_deck_tags).post(tags::add_deck_tags))
+        .route("/{deck_id}/tags/{tag}", delete(tags::remove_deck_tag))
         .route("/{deck_id}/study", get(deck::start_study_session))
         .route("/due", get(deck::get_all_due_words))
         .route("/due-count", get(deck::get_due_words_count))
         .route("/{deck_id}/words/{word_id}/review", post(deck::record_word_review))
-        .route("/{deck_id}", get(deck::view_deck))
+        .route("/{deck_id}/words/{word_id}/notes", put(deck::update_deck_word_notes))
+        .route("/{deck_id}", get(deck::view_deck).patch(deck::update_deck))
+        .route("/{deck_id}/export/anki", get(deck::export_deck_anki))
+        .route("/{deck_id}/export/csv", get(deck::export_deck_csv))
+        .with_state(pool.clone())
+        .layer(session_layer.clone());
+
+    let public_deck_api_router = Router::new()
+        .route("/", get(public::list_public_decks))
+        .route("/liked", get(public::list_liked_decks))
+        .route("/subscribed", get(public::list_subscribed_decks))
+        .route("/{deck_id}", get(public::view_public_deck))
+        .route("/{deck_id}/clone", post(public::clone_public_deck))
+        .route("/{deck_id}/like", post(public::like_deck).delete(public::unlike_deck))
+        .route("/{deck_id}/subscribe", post(public::subscribe_deck).delete(public::unsubscribe_deck))
+        .with_state(pool.clone())
+        .layer(session_layer.clone());
+
+    let tag_api_router = Router::new()
+        .route("/autocomplete", get(tags::suggest_tags))
         .with_state(pool.clone())
         .layer(session_layer.clone());
 
     let search_api_router = Router::new()
         .route("/", get(search::search_api))
-        .with_state((pool.clone(), dict_data.clone()))
+        .with_state((pool.clone(), dictionary.clone()))
+        .layer(session_layer.clone());
+
+    let dictionary_api_router = Router::new()
+        .route("/", get(search::dictionary_info))
+        .with_state(dictionary.clone());
+
+    let segment_api_router = Router::new()
+        .route("/", get(segment::segment_api))
+        .with_state((pool.clone(), dictionary.clone()))
+        .layer(session_layer.clone());
+
+    let import_api_router = Router::new()
+        .route("/subtitles/preview", post(import::preview_subtitle_import))
+        .route("/subtitles", post(import::import_subtitle_deck))
+        .route("/anki/preview", post(import::preview_anki_import))
+        .route("/anki", post(import::import_anki_deck))
+        .route("/csv/preview", post(import::preview_word_list_import))
+        .route("/csv", post(import::import_word_list))
+        .with_state((pool.clone(), dictionary.clone()))
+        .layer(DefaultBodyLimit::max(import::MAX_UPLOAD_BYTES))
+        .layer(session_layer.clone());
+
+    let settings_api_router = Router::new()
+        .route("/srs", get(settings::get_srs_settings).put(settings::update_srs_settings))
+        .with_state(pool.clone())
         .layer(session_layer.clone());
 
     let api_router = Router::new()
         .nest("/decks", deck_api_router)
+        .nest("/public-decks", public_deck_api_router)
+        .nest("/tags", tag_api_router)
         .nest("/search", search_api_router)
+        .nest("/dictionary", dictionary_api_router)
+        .nest("/segment", segment_api_router)
+        .nest("/import", import_api_router)
+        .nest("/settings", settings_api_router)
         .layer(session_layer.clone());
 
     let auth_router = Router::new()
@@ -112,20 +223,20 @@ async fn main() {
         // API routes
         .nest("/api", api_router)
         // Static files
-        .nest_service("/static", get_service(ServeDir::new("src/static")))
+        .nest_service("/static", get_service(ServeDir::new(&config.paths.static_files)))
         // Shared state and layers
         .layer(Extension(templates))
         .layer(session_layer);
 
     // Start server
-    let listener = TcpListener::bind("127.0.0.1:5000")
+    let listener = TcpListener::bind(config.server.bind)
         .await
         .unwrap_or_else(|e| {
             eprintln!("Failed to bind to address: {}", e);
             std::process::exit(1);
         });
 
-    println!("Server running on http://localhost:5000");
+    println!("Server running on http://{}", config.server.bind);
 
     axum::serve(listener, app).await.unwrap_or_else(|e| {
         eprintln!("Server error: {}", e);
@@ -151,25 +262,22 @@ async fn root_handler(
 
 async fn dashboard(
     Extension(templates): Extension<Arc<Tera>>,
-    session: tower_sessions::Session,
+    _user: CurrentUser,
 ) -> impl IntoResponse {
     let mut context = tera::Context::new();
-    context.insert("logged_in", &utils::is_logged_in(&session).await);
+    context.insert("logged_in", &true);
     utils::render_template(&templates, "dashboard.html", context).into_response()
 }
 
 async fn public_decks_management(
     Extension(templates): Extension<Arc<Tera>>,
-    session: tower_sessions::Session,
+    user: Option<CurrentUser>,
 ) -> impl IntoResponse {
     let mut context = tera::Context::new();
-    let logged_in = utils::is_logged_in(&session).await;
-    context.insert("logged_in", &logged_in);
+    context.insert("logged_in", &user.is_some());
 
-    if logged_in {
-        if let Some(user_id) = utils::get_current_user_id(&session).await {
-            context.insert("user_id", &user_id);
-        }
+    if let Some(CurrentUser(user_id)) = user {
+        context.insert("user_id", &user_id);
     }
 
     utils::render_template(&templates, "public-decks-list.html", context).into_response()
@@ -177,17 +285,11 @@ async fn public_decks_management(
 
 async fn decks_management(
     Extension(templates): Extension<Arc<Tera>>,
-    session: tower_sessions::Session,
+    CurrentUser(user_id): CurrentUser,
 ) -> impl IntoResponse {
     let mut context = tera::Context::new();
-    let logged_in = utils::is_logged_in(&session).await;
-    context.insert("logged_in", &logged_in);
-
-    if logged_in {
-        if let Some(user_id) = utils::get_current_user_id(&session).await {
-            context.insert("user_id", &user_id);
-        }
-    }
+    context.insert("logged_in", &true);
+    context.insert("user_id", &user_id);
 
     utils::render_template(&templates, "decks-management.html", context).into_response()
 }
@@ -239,10 +341,10 @@ async fn handle_logout(session: tower_sessions::Session) -> Result<Redirect, dat
 async fn deck_view_page(
     Path(deck_id): Path<i32>,
     Extension(templates): Extension<Arc<Tera>>,
-    session: tower_sessions::Session,
+    _user: CurrentUser,
 ) -> impl IntoResponse {
     let mut context = tera::Context::new();
-    context.insert("logged_in", &utils::is_logged_in(&session).await);
+    context.insert("logged_in", &true);
     context.insert("deck_id", &deck_id);
     utils::render_template(&templates, "view-deck.html", context).into_response()
 }
@@ -251,10 +353,10 @@ async fn deck_view_page(
 pub async fn study_page(
     Path(deck_id): Path<i32>,
     Extension(templates): Extension<Arc<Tera>>,
-    session: tower_sessions::Session,
+    _user: CurrentUser,
 ) -> impl IntoResponse {
     let mut context = tera::Context::new();
-    context.insert("logged_in", &utils::is_logged_in(&session).await);
+    context.insert("logged_in", &true);
     context.insert("deck_id", &deck_id);
 
     match templates.render("study-deck.html", &context) {
@@ -269,15 +371,10 @@ pub async fn study_page(
 // Handler for due reviews (no deck ID)
 pub async fn due_reviews_page(
     Extension(templates): Extension<Arc<Tera>>,
-    session: tower_sessions::Session,
+    _user: CurrentUser,
 ) -> impl IntoResponse {
     let mut context = tera::Context::new();
-    let logged_in = utils::is_logged_in(&session).await;
-    context.insert("logged_in", &logged_in);
-
-    if !logged_in {
-        return Redirect::to("/auth/login").into_response();
-    }
+    context.insert("logged_in", &true);
 
     match templates.render("study-deck.html", &context) {
         Ok(html) => Html(html).into_response(),
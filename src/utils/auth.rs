@@ -0,0 +1,99 @@
+use axum::{
+    extract::{FromRef, FromRequestParts, OptionalFromRequestParts, OriginalUri, RawPathParams},
+    http::request::Parts,
+    response::{IntoResponse, Redirect, Response},
+};
+use diesel::prelude::*;
+use tower_sessions::Session;
+
+use crate::{DbPool, data::models::AppError, schema::decks, utils::get_current_user_id};
+
+/// The logged in user. Without one, API requests are rejected with 401 and
+/// page requests are redirected to the login page.
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentUser(pub i32);
+
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match <CurrentUser as OptionalFromRequestParts<S>>::from_request_parts(parts, state).await? {
+            Some(user) => Ok(user),
+            None if is_api_request(parts) => Err(AppError::Unauthorized.into_response()),
+            None => Err(Redirect::to("/auth/login").into_response()),
+        }
+    }
+}
+
+/// `Option<CurrentUser>` for routes that also serve anonymous visitors
+impl<S: Send + Sync> OptionalFromRequestParts<S> for CurrentUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        let session = Session::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(get_current_user_id(&session).await.map(CurrentUser))
+    }
+}
+
+/// A deck owned by the logged in user, taken from the `deck_id` path parameter
+#[derive(Debug, Clone, Copy)]
+pub struct OwnedDeck {
+    pub deck_id: i32,
+    pub user_id: i32,
+}
+
+impl<S> FromRequestParts<S> for OwnedDeck
+where
+    DbPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let CurrentUser(user_id) =
+            <CurrentUser as FromRequestParts<S>>::from_request_parts(parts, state).await?;
+
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let deck_id = params
+            .iter()
+            .find(|(name, _)| *name == "deck_id")
+            .and_then(|(_, value)| value.parse::<i32>().ok())
+            .ok_or_else(|| AppError::BadRequest("Invalid deck id".to_string()).into_response())?;
+
+        let mut conn = DbPool::from_ref(state).get().map_err(|e| AppError::from(e).into_response())?;
+        verify_deck_owner(&mut conn, deck_id, user_id).map_err(IntoResponse::into_response)?;
+
+        Ok(OwnedDeck { deck_id, user_id })
+    }
+}
+
+/// Owner of a deck, failing with NOT_FOUND if the deck does not exist
+pub fn deck_owner(conn: &mut SqliteConnection, deck_id: i32) -> Result<i32, AppError> {
+    decks::table
+        .filter(decks::deck_id.eq(deck_id))
+        .select(decks::user_id)
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("Deck not found".to_string()))
+}
+
+/// Fails with FORBIDDEN unless the deck belongs to the user
+pub fn verify_deck_owner(conn: &mut SqliteConnection, deck_id: i32, user_id: i32) -> Result<(), AppError> {
+    if deck_owner(conn, deck_id)? != user_id {
+        return Err(AppError::Forbidden("Access denied".to_string()));
+    }
+    Ok(())
+}
+
+/// Whether the request was made to the JSON API rather than for a page
+fn is_api_request(parts: &Parts) -> bool {
+    let path = match parts.extensions.get::<OriginalUri>() {
+        Some(OriginalUri(uri)) => uri.path(),
+        None => parts.uri.path(),
+    };
+    path.starts_with("/api/")
+}
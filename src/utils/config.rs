@@ -0,0 +1,128 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::data::models::{AppConfig, ConfigError};
+
+/// Config file read when `ZWCD_CONFIG` is not set
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+/// Longest session inactivity allowed, keeping cookie expiry dates in range
+pub const MAX_SESSION_EXPIRY_DAYS: i64 = 3650;
+
+impl AppConfig {
+    /// Reads the config file, applies environment overrides and validates the
+    /// result. Without `ZWCD_CONFIG`, a missing `config.toml` means defaults.
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, explicit) = match env_var("ZWCD_CONFIG") {
+            Some(path) => (PathBuf::from(path), true),
+            None => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+        };
+
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).map_err(|source| ConfigError::Parse {
+                path: path.clone(),
+                source,
+            })?,
+            Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => AppConfig::default(),
+            Err(source) => return Err(ConfigError::Io { path, source }),
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Glob matching every template under the templates directory
+    pub fn template_glob(&self) -> String {
+        self.paths.templates.join("**/*.html").to_string_lossy().into_owned()
+    }
+
+    /// Overrides file settings with any `ZWCD_*` variables that are set
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Some(bind) = env_var("ZWCD_BIND") {
+            self.server.bind = parse_env("ZWCD_BIND", bind)?;
+        }
+        // DATABASE_URL is also read by the diesel CLI
+        if let Some(url) = env_var("ZWCD_DATABASE_URL").or_else(|| env_var("DATABASE_URL")) {
+            self.database.url = url;
+        }
+        if let Some(auto) = env_var("ZWCD_AUTO_MIGRATE") {
+            self.database.auto_migrate = parse_env("ZWCD_AUTO_MIGRATE", auto)?;
+        }
+        if let Some(path) = env_var("ZWCD_DICTIONARY_PATH") {
+            self.dictionary.path = PathBuf::from(path);
+        }
+        if let Some(path) = env_var("ZWCD_TEMPLATES_DIR") {
+            self.paths.templates = PathBuf::from(path);
+        }
+        if let Some(path) = env_var("ZWCD_STATIC_DIR") {
+            self.paths.static_files = PathBuf::from(path);
+        }
+        if let Some(days) = env_var("ZWCD_SESSION_EXPIRY_DAYS") {
+            self.session.expiry_days = parse_env("ZWCD_SESSION_EXPIRY_DAYS", days)?;
+        }
+        if let Some(secure) = env_var("ZWCD_SESSION_SECURE") {
+            self.session.secure = parse_env("ZWCD_SESSION_SECURE", secure)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the settings every command needs, leaving files used only when
+    /// serving to `validate_serving` so `migrate` runs with just a database
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.database.url.trim().is_empty() {
+            return invalid("database.url", "must not be empty".to_string());
+        }
+        if !(1..=MAX_SESSION_EXPIRY_DAYS).contains(&self.session.expiry_days) {
+            return invalid(
+                "session.expiry_days",
+                format!("must be between 1 and {}", MAX_SESSION_EXPIRY_DAYS),
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that the dictionary, templates and static files exist
+    pub fn validate_serving(&self) -> Result<(), ConfigError> {
+        if !self.dictionary.path.is_file() {
+            return invalid(
+                "dictionary.path",
+                format!("{} is not a file", self.dictionary.path.display()),
+            );
+        }
+        if !self.paths.templates.is_dir() {
+            return invalid(
+                "paths.templates",
+                format!("{} is not a directory", self.paths.templates.display()),
+            );
+        }
+        if !self.paths.static_files.is_dir() {
+            return invalid(
+                "paths.static_files",
+                format!("{} is not a directory", self.paths.static_files.display()),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn invalid(field: &'static str, reason: String) -> Result<(), ConfigError> {
+    Err(ConfigError::Invalid { field, reason })
+}
+
+/// Value of an environment variable, treating an empty value as unset
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn parse_env<T>(name: &'static str, value: String) -> Result<T, ConfigError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|e: T::Err| ConfigError::Env {
+        name,
+        reason: e.to_string(),
+        value,
+    })
+}
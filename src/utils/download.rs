@@ -0,0 +1,35 @@
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+/// Builds a file download response. The file name is sent both as an ASCII
+/// fallback and percent-encoded UTF-8 so deck names in hanzi survive.
+pub fn attachment(body: Vec<u8>, content_type: &'static str, file_name: &str) -> Response {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' })
+        .collect();
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"._-".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect();
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
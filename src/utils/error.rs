@@ -0,0 +1,67 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::data::models::AppError;
+
+impl AppError {
+    /// HTTP status the error is answered with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) | AppError::Database(diesel::result::Error::NotFound) => {
+                StatusCode::NOT_FOUND
+            }
+            AppError::BadRequest(_) | AppError::Validation { .. } => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Database(_) | AppError::Pool(_) | AppError::Session(_) | AppError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Unauthorized => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) | AppError::Database(diesel::result::Error::NotFound) => "not_found",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation { .. } => "validation_failed",
+            AppError::Conflict(_) => "conflict",
+            AppError::Database(_) | AppError::Pool(_) => "database_error",
+            AppError::Session(_) | AppError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+/// Renders `{"code", "message", "status", "fields"?}`. Server-side failures
+/// are logged and answered with a generic message instead of their details.
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let message = match &self {
+            AppError::Database(diesel::result::Error::NotFound) => "Not found".to_string(),
+            _ if status.is_server_error() => {
+                log::error!("Request failed: {}", self);
+                "Something went wrong, please try again later".to_string()
+            }
+            _ => self.to_string(),
+        };
+
+        let mut body = json!({
+            "code": self.code(),
+            "message": message,
+            "status": status.as_u16(),
+        });
+        if let AppError::Validation { fields, .. } = &self {
+            body["fields"] = json!(fields);
+        }
+
+        (status, Json(body)).into_response()
+    }
+}
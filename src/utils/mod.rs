@@ -1,5 +1,13 @@
+pub mod auth;
+pub mod config;
+pub mod download;
+pub mod error;
 pub mod session;
+pub mod sql;
 pub mod template;
 
+pub use auth::*;
+pub use download::*;
 pub use session::*;
+pub use sql::*;
 pub use template::*;
\ No newline at end of file
@@ -0,0 +1,4 @@
+/// Escapes `%`, `_` and `\` so text matches literally in a `LIKE ... ESCAPE '\'` pattern
+pub fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}